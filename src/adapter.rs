@@ -0,0 +1,196 @@
+use std::fmt;
+
+use wgpu::{Adapter, Backends, Instance};
+
+/// Which adapter the user asked for on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdapterChoice {
+    /// Position in the list printed by `--list-adapters`.
+    Index(usize),
+    /// Case-insensitive substring of the adapter name, e.g. `llvmpipe`.
+    Name(String),
+}
+
+impl AdapterChoice {
+    pub fn parse(value: &str) -> Self {
+        match value.parse() {
+            Ok(index) => AdapterChoice::Index(index),
+            Err(_) => AdapterChoice::Name(value.to_string()),
+        }
+    }
+
+    /// Whether the adapter at `index` in the list, called `name`, is the one asked for.
+    fn matches(&self, index: usize, name: &str) -> bool {
+        match self {
+            AdapterChoice::Index(i) => *i == index,
+            AdapterChoice::Name(wanted) => name.to_lowercase().contains(&wanted.to_lowercase()),
+        }
+    }
+}
+
+impl fmt::Display for AdapterChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdapterChoice::Index(i) => write!(f, "#{}", i),
+            AdapterChoice::Name(name) => write!(f, "`{}`", name),
+        }
+    }
+}
+
+/// How the [`App`](crate::app::App) should pick its adapter.
+#[derive(Clone, Debug)]
+pub struct AdapterOptions {
+    pub backends: Backends,
+    pub choice: Option<AdapterChoice>,
+}
+
+impl Default for AdapterOptions {
+    fn default() -> Self {
+        Self {
+            backends: Backends::all(),
+            choice: None,
+        }
+    }
+}
+
+/// Parse a comma separated backend list like `vulkan,gl`.
+pub fn parse_backends(value: &str) -> Result<Backends, String> {
+    value
+        .split(',')
+        .try_fold(Backends::empty(), |backends, name| {
+            let backend = match name.trim().to_lowercase().as_str() {
+                "vulkan" | "vk" => Backends::VULKAN,
+                "gl" | "opengl" | "gles" => Backends::GL,
+                "metal" => Backends::METAL,
+                "dx12" | "d3d12" => Backends::DX12,
+                "dx11" | "d3d11" => Backends::DX11,
+                "webgpu" | "browser" => Backends::BROWSER_WEBGPU,
+                "primary" => Backends::PRIMARY,
+                "secondary" => Backends::SECONDARY,
+                "all" => Backends::all(),
+                _ => return Err(format!("unknown backend `{}`", name.trim())),
+            };
+            Ok(backends | backend)
+        })
+}
+
+/// All adapters on the given backends, in the order `--adapter <index>` refers to them.
+pub fn enumerate(instance: &Instance, backends: Backends) -> Vec<Adapter> {
+    instance.enumerate_adapters(backends).collect()
+}
+
+/// Find the adapter matching `choice` among [`enumerate`]'d adapters.
+pub fn select(instance: &Instance, backends: Backends, choice: &AdapterChoice) -> Option<Adapter> {
+    enumerate(instance, backends)
+        .into_iter()
+        .enumerate()
+        .find(|(index, adapter)| choice.matches(*index, &adapter.get_info().name))
+        .map(|(_, adapter)| adapter)
+}
+
+/// Print every adapter on the given backends with its most relevant limits.
+pub fn list(backends: Backends) {
+    let instance = Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+
+    let adapters = enumerate(&instance, backends);
+    if adapters.is_empty() {
+        println!("no adapters found");
+        return;
+    }
+
+    for (index, adapter) in adapters.iter().enumerate() {
+        let info = adapter.get_info();
+        let limits = adapter.limits();
+
+        println!("[{}] {}", index, info.name);
+        println!("    backend:     {:?}", info.backend);
+        println!("    device type: {:?}", info.device_type);
+        println!("    vendor:      {:#06x}", info.vendor);
+        println!("    device:      {:#06x}", info.device);
+        println!("    driver:      {} {}", info.driver, info.driver_info);
+        println!("    limits:");
+        println!(
+            "      max_texture_dimension_2d:  {}",
+            limits.max_texture_dimension_2d
+        );
+        println!(
+            "      max_texture_array_layers:  {}",
+            limits.max_texture_array_layers
+        );
+        println!(
+            "      max_bind_groups:           {}",
+            limits.max_bind_groups
+        );
+        println!(
+            "      max_buffer_size:           {}",
+            limits.max_buffer_size
+        );
+        println!(
+            "      max_uniform_buffer_size:   {}",
+            limits.max_uniform_buffer_binding_size
+        );
+        println!(
+            "      max_storage_buffer_size:   {}",
+            limits.max_storage_buffer_binding_size
+        );
+        println!(
+            "      max_vertex_buffers:        {}",
+            limits.max_vertex_buffers
+        );
+        println!(
+            "      max_vertex_attributes:     {}",
+            limits.max_vertex_attributes
+        );
+        println!(
+            "      max_push_constant_size:    {}",
+            limits.max_push_constant_size
+        );
+        println!(
+            "      max_compute_workgroups:    {}",
+            limits.max_compute_workgroups_per_dimension
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choices_are_an_index_or_a_name_substring() {
+        assert_eq!(AdapterChoice::parse("1"), AdapterChoice::Index(1));
+        assert_eq!(
+            AdapterChoice::parse("llvm"),
+            AdapterChoice::Name("llvm".to_owned())
+        );
+        // negative numbers are not positions in the list
+        assert_eq!(
+            AdapterChoice::parse("-1"),
+            AdapterChoice::Name("-1".to_owned())
+        );
+
+        assert!(AdapterChoice::Index(1).matches(1, "llvmpipe (LLVM 15.0.7, 256 bits)"));
+        assert!(!AdapterChoice::Index(1).matches(0, "llvmpipe (LLVM 15.0.7, 256 bits)"));
+        let name = AdapterChoice::parse("LLVMpipe");
+        assert!(name.matches(3, "llvmpipe (LLVM 15.0.7, 256 bits)"));
+        assert!(!name.matches(3, "NVIDIA GeForce RTX 3070"));
+    }
+
+    #[test]
+    fn parses_backend_lists() {
+        assert_eq!(parse_backends("vulkan"), Ok(Backends::VULKAN));
+        assert_eq!(
+            parse_backends("Vulkan, gl,dx12"),
+            Ok(Backends::VULKAN | Backends::GL | Backends::DX12)
+        );
+        assert_eq!(parse_backends("all"), Ok(Backends::all()));
+        assert_eq!(
+            parse_backends("vulkan,glide"),
+            Err("unknown backend `glide`".to_owned())
+        );
+        assert_eq!(parse_backends(""), Err("unknown backend ``".to_owned()));
+    }
+}
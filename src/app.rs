@@ -1,26 +1,47 @@
-use std::mem::size_of;
+use std::{
+    error::Error,
+    fmt,
+    mem::size_of,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use bytemuck::{cast_slice, Pod, Zeroable};
-use image::GenericImageView;
 use wgpu::{
     include_wgsl,
     util::{BufferInitDescriptor, DeviceExt},
-    vertex_attr_array, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BlendState,
-    BufferAddress, BufferUsages, Color, ColorWrites, DeviceDescriptor, Extent3d, FragmentState,
-    FrontFace, Instance, InstanceDescriptor, LoadOp, MultisampleState, Operations,
-    PipelineLayoutDescriptor, PolygonMode, PowerPreference, PrimitiveState, PrimitiveTopology,
-    RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
-    RequestAdapterOptions, ShaderStages, SurfaceConfiguration, SurfaceError, Texture,
+    AdapterInfo, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BlendState,
+    BufferAddress, BufferUsages, Color, ColorWrites, CreateSurfaceError, DeviceDescriptor,
+    Extent3d, FragmentState, FrontFace, Instance, InstanceDescriptor, LoadOp, MultisampleState,
+    Operations, PipelineLayoutDescriptor, PolygonMode, PowerPreference, PrimitiveState,
+    PrimitiveTopology, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+    RequestAdapterOptions, RequestDeviceError, ShaderStages, SurfaceConfiguration, SurfaceError,
     TextureAspect, TextureDescriptor, TextureUsages, VertexAttribute, VertexBufferLayout,
     VertexState, VertexStepMode,
 };
-use winit::{dpi::PhysicalSize, event::WindowEvent, window::Window};
+use wgpu_core::device::DeviceError;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent},
+    window::Window,
+};
+
+use crate::{
+    adapter::{self, AdapterChoice, AdapterOptions},
+    camera::{Camera, CameraUniform},
+    text::{Align, Text, TextPipeline, TextStyle},
+    ui::{Ui, UiPipeline},
+};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct Vertex {
-    position: [f32; 3],
-    color: [f32; 3],
+    pub position: [f32; 3],
+    /// Linear RGB.
+    pub color: [f32; 3],
 }
 
 impl Vertex {
@@ -44,7 +65,7 @@ impl Vertex {
     }
 }
 
-const VERTICES: &[Vertex] = &[
+pub const VERTICES: &[Vertex] = &[
     Vertex {
         position: [-0.0868241, 0.49240386, 0.0],
         color: [0.5, 0.0, 0.5],
@@ -67,62 +88,172 @@ const VERTICES: &[Vertex] = &[
     }, // E
 ];
 
-const INDICES: &[u16] = &[0, 1, 4, 1, 2, 4, 2, 3, 4];
+pub const INDICES: &[u16] = &[0, 1, 4, 1, 2, 4, 2, 3, 4];
+
+/// Everything that can go wrong while setting up the [`App`].
+#[derive(Debug)]
+pub enum AppError {
+    /// The window could not be turned into a surface.
+    CreateSurface(CreateSurfaceError),
+    /// No adapter is compatible with the surface.
+    NoAdapter,
+    /// The adapter requested with `--adapter` does not exist.
+    AdapterNotFound(AdapterChoice),
+    /// The adapter refused to hand out a device.
+    RequestDevice {
+        adapter: AdapterInfo,
+        source: RequestDeviceError,
+    },
+    /// The adapter can not present to the surface.
+    UnsupportedSurface { adapter: AdapterInfo },
+    /// A bundled asset could not be decoded.
+    DecodeAsset {
+        name: &'static str,
+        source: image::ImageError,
+    },
+    /// The bundled font could not be parsed.
+    LoadFont {
+        name: &'static str,
+        reason: &'static str,
+    },
+}
 
-pub struct App {
-    pub size: PhysicalSize<u32>,
-    surface_config: SurfaceConfiguration,
-    window: Window,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    render_pipeline: wgpu::RenderPipeline,
-    surface: wgpu::Surface,
-    vertex_buffer: wgpu::Buffer,
-    num_vertices: u32,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
-    texture_bind_group: wgpu::BindGroup,
+impl AppError {
+    /// The adapter that was in use when the error happened, if we got that far.
+    pub fn adapter_info(&self) -> Option<&AdapterInfo> {
+        match self {
+            AppError::RequestDevice { adapter, .. } | AppError::UnsupportedSurface { adapter } => {
+                Some(adapter)
+            }
+            _ => None,
+        }
+    }
 }
 
-impl App {
-    pub async fn new(window: Window) -> Self {
-        let size = window.inner_size();
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::CreateSurface(_) => write!(f, "failed to create a surface for the window"),
+            AppError::NoAdapter => write!(f, "no graphics adapter is compatible with the window"),
+            AppError::AdapterNotFound(choice) => {
+                write!(f, "no adapter matches {} (see `--list-adapters`)", choice)
+            }
+            AppError::RequestDevice { adapter, .. } => {
+                write!(
+                    f,
+                    "failed to request a device from adapter `{}`",
+                    adapter.name
+                )
+            }
+            AppError::UnsupportedSurface { adapter } => write!(
+                f,
+                "adapter `{}` does not support presenting to the window",
+                adapter.name
+            ),
+            AppError::DecodeAsset { name, .. } => write!(f, "failed to decode asset `{}`", name),
+            AppError::LoadFont { name, reason } => {
+                write!(f, "failed to load font `{}`: {}", name, reason)
+            }
+        }
+    }
+}
 
-        // create gpu instance with all backends
-        let instance = Instance::new(InstanceDescriptor::default());
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::CreateSurface(e) => Some(e),
+            AppError::RequestDevice { source, .. } => Some(source),
+            AppError::DecodeAsset { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
-        // create surface from window
-        let surface = unsafe { instance.create_surface(&window) }.unwrap();
+/// CPU-side copy of everything the GPU resources are built from, so they can be
+/// rebuilt from scratch after the device is lost.
+pub struct SceneData {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    diffuse: image::RgbaImage,
+}
 
-        // create adapter
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false, // don't fallback to software
-            })
-            .await
-            .unwrap();
+impl SceneData {
+    pub fn load() -> Result<Self, AppError> {
+        let diffuse_bytes = include_bytes!("../assets/texture.png");
+        let diffuse_image =
+            image::load_from_memory(diffuse_bytes).map_err(|source| AppError::DecodeAsset {
+                name: "texture.png",
+                source,
+            })?;
+
+        Ok(Self {
+            vertices: VERTICES.to_vec(),
+            indices: INDICES.to_vec(),
+            diffuse: diffuse_image.to_rgba8(),
+        })
+    }
+}
 
-        // create device and queue
-        let (device, queue) = adapter
-            .request_device(&DeviceDescriptor::default(), None)
-            .await
-            .unwrap();
+/// How the scene is shaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    VertexColor,
+    Texture,
+}
 
-        // create surface configuration
-        let surface_config = surface
-            .get_default_config(&adapter, size.width, size.height)
-            .unwrap();
+impl RenderMode {
+    const ALL: [RenderMode; 2] = [RenderMode::VertexColor, RenderMode::Texture];
 
-        // config surface
-        surface.configure(&device, &surface_config);
+    fn label(self) -> &'static str {
+        match self {
+            RenderMode::VertexColor => "Vertex color",
+            RenderMode::Texture => "Texture",
+        }
+    }
+}
 
-        let diffuse_bytes = include_bytes!("../assets/texture.png");
-        let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
-        let diffuse_rgba = diffuse_image.to_rgb8();
-        let dimensions = diffuse_image.dimensions();
-        
+/// Everything the debug UI can tweak.
+pub struct DebugSettings {
+    /// sRGB, as shown in the color picker.
+    pub clear_color: [f32; 3],
+    pub render_mode: RenderMode,
+    pub camera: Camera,
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        Self {
+            clear_color: [0.0, 0.0, 0.0],
+            render_mode: RenderMode::VertexColor,
+            camera: Camera::default(),
+        }
+    }
+}
+
+/// Everything that lives on the device and has to be recreated with it.
+struct GpuResources {
+    color_pipeline: wgpu::RenderPipeline,
+    texture_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+    texture_bind_group: wgpu::BindGroup,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    text: TextPipeline,
+    ui: UiPipeline,
+}
+
+impl GpuResources {
+    fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        scene: &SceneData,
+        text: &mut Text,
+        ui: &mut Ui,
+    ) -> Self {
+        let dimensions = scene.diffuse.dimensions();
         let texture_size = Extent3d {
             width: dimensions.0,
             height: dimensions.1,
@@ -139,21 +270,21 @@ impl App {
             view_formats: &[],
         });
 
-        // queue.write_texture(
-        //     wgpu::ImageCopyTexture {
-        //         texture: &diffuse_texture,
-        //         mip_level: 0,
-        //         origin: wgpu::Origin3d::ZERO,
-        //         aspect: TextureAspect::All,
-        //     },
-        //     &diffuse_rgba,
-        //     wgpu::ImageDataLayout {
-        //         offset: 0,
-        //         bytes_per_row: Some(4 * dimensions.0),
-        //         rows_per_image: Some(dimensions.1),
-        //     },
-        //     texture_size,
-        // );
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &diffuse_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &scene.diffuse,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * dimensions.0),
+                rows_per_image: Some(dimensions.1),
+            },
+            texture_size,
+        );
 
         let diffuse_texture_view =
             diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -207,75 +338,263 @@ impl App {
             ],
         });
 
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Camera Buffer"),
+            size: size_of::<CameraUniform>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Camera Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let camera_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
         let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&texture_bindgroup_layout],
+            bind_group_layouts: &[&texture_bindgroup_layout, &camera_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: VertexState {
-                module: &shader,
-                entry_point: "vert_main",
-                buffers: &[
-                    Vertex::desc(), // vertex buffer layout
-                ],
-            },
-            fragment: Some(FragmentState {
-                module: &shader,
-                entry_point: "frag_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_config.format,
-                    blend: Some(BlendState::REPLACE),
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            primitive: PrimitiveState {
-                topology: PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            multiview: None,
-        });
+        // one pipeline per render mode, they only differ in the fragment shader
+        let create_pipeline = |label, entry_point| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: "vert_main",
+                    buffers: &[
+                        Vertex::desc(), // vertex buffer layout
+                    ],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: Some(BlendState::REPLACE),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth: false,
+                    polygon_mode: PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let color_pipeline = create_pipeline("Vertex Color Pipeline", "frag_main");
+        let texture_pipeline = create_pipeline("Texture Pipeline", "frag_texture");
 
         let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Vertex Buffer"),
-            contents: cast_slice(VERTICES),
+            contents: cast_slice(&scene.vertices),
             usage: BufferUsages::VERTEX,
         });
 
-        let num_vertices = VERTICES.len() as u32;
-
         let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Index Buffer"),
-            contents: cast_slice(INDICES),
+            contents: cast_slice(&scene.indices),
             usage: BufferUsages::INDEX,
         });
 
-        let num_indices = INDICES.len() as u32;
+        let num_indices = scene.indices.len() as u32;
 
         Self {
+            color_pipeline,
+            texture_pipeline,
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            texture_bind_group,
+            camera_buffer,
+            camera_bind_group,
+            text: TextPipeline::new(device, queue, format, text),
+            ui: UiPipeline::new(device, queue, format, ui),
+        }
+    }
+}
+
+/// Frame rate averaged over half a second windows.
+struct FrameStats {
+    window_start: Instant,
+    frames: u32,
+    fps: f32,
+    frame_time: Duration,
+}
+
+impl FrameStats {
+    const WINDOW: Duration = Duration::from_millis(500);
+
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            frames: 0,
+            fps: 0.0,
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.frames += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Self::WINDOW {
+            self.fps = self.frames as f32 / elapsed.as_secs_f32();
+            self.frame_time = elapsed / self.frames;
+            self.frames = 0;
+            self.window_start = Instant::now();
+        }
+    }
+}
+
+pub struct App {
+    pub size: PhysicalSize<u32>,
+    surface_config: SurfaceConfiguration,
+    window: Window,
+    instance: Instance,
+    options: AdapterOptions,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    device_lost: Arc<AtomicBool>,
+    surface: wgpu::Surface,
+    scene: SceneData,
+    text: Text,
+    ui: Ui,
+    settings: DebugSettings,
+    resources: GpuResources,
+    stats: FrameStats,
+}
+
+impl App {
+    pub async fn new(window: Window, options: &AdapterOptions) -> Result<Self, AppError> {
+        let size = window.inner_size();
+
+        // create gpu instance with the requested backends
+        let instance = Instance::new(InstanceDescriptor {
+            backends: options.backends,
+            ..Default::default()
+        });
+
+        // create surface from window
+        let surface =
+            unsafe { instance.create_surface(&window) }.map_err(AppError::CreateSurface)?;
+
+        let adapter = pick_adapter(&instance, &surface, options).await?;
+
+        // create device and queue
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let (device, queue) = request_device(&adapter, &device_lost).await?;
+
+        // create surface configuration
+        let surface_config = surface
+            .get_default_config(&adapter, size.width, size.height)
+            .ok_or_else(|| AppError::UnsupportedSurface {
+                adapter: adapter.get_info(),
+            })?;
+
+        // config surface
+        surface.configure(&device, &surface_config);
+
+        let scene = SceneData::load()?;
+        let mut text = Text::new()?;
+        let mut ui = Ui::new(&window);
+        let resources = GpuResources::new(
+            &device,
+            &queue,
+            surface_config.format,
+            &scene,
+            &mut text,
+            &mut ui,
+        );
+
+        Ok(Self {
             size,
             window,
+            instance,
+            options: options.clone(),
+            adapter,
             device,
             queue,
+            device_lost,
             surface,
             surface_config,
-            render_pipeline,
-            vertex_buffer,
-            num_vertices,
-            index_buffer,
-            num_indices,
-            texture_bind_group,
-        }
+            scene,
+            text,
+            ui,
+            settings: DebugSettings::default(),
+            resources,
+            stats: FrameStats::new(),
+        })
+    }
+
+    /// Whether the error handler saw the device go away since the last [`App::recover`].
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Relaxed)
+    }
+
+    /// Recreate the device, queue and every GPU resource from the retained
+    /// [`SceneData`]. Falls back to picking a new adapter if the current one
+    /// can no longer hand out a device.
+    pub async fn recover(&mut self) -> Result<(), AppError> {
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let (device, queue) = match request_device(&self.adapter, &device_lost).await {
+            Ok(device) => device,
+            Err(err) => {
+                eprintln!("warning: {}, picking a new adapter", err);
+                self.adapter = pick_adapter(&self.instance, &self.surface, &self.options).await?;
+                request_device(&self.adapter, &device_lost).await?
+            }
+        };
+
+        // a new adapter may prefer, or only support, another surface format
+        self.surface_config = self
+            .surface
+            .get_default_config(&self.adapter, self.size.width, self.size.height)
+            .ok_or_else(|| AppError::UnsupportedSurface {
+                adapter: self.adapter.get_info(),
+            })?;
+        self.surface.configure(&device, &self.surface_config);
+        self.resources = GpuResources::new(
+            &device,
+            &queue,
+            self.surface_config.format,
+            &self.scene,
+            &mut self.text,
+            &mut self.ui,
+        );
+        self.device = device;
+        self.queue = queue;
+        self.device_lost = device_lost;
+
+        Ok(())
     }
 
     pub fn window(&self) -> &Window {
@@ -292,18 +611,133 @@ impl App {
     }
 
     pub fn input(&mut self, event: &WindowEvent) -> bool {
+        if self.ui.on_event(event) {
+            return true;
+        }
+
+        // F1 toggles the debug UI
+        if let WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::F1),
+                    ..
+                },
+            ..
+        } = event
+        {
+            self.ui.visible = !self.ui.visible;
+            return true;
+        }
+
         false
     }
 
-    pub fn update(&mut self) {}
+    /// Queue `text` to be drawn over the scene this frame, `position` is the top
+    /// left corner in physical pixels.
+    pub fn draw_text(&mut self, text: &str, position: [f32; 2], style: &TextStyle) {
+        self.text.queue(text, position, style);
+    }
+
+    pub fn update(&mut self) {
+        self.stats.tick();
+
+        let width = self.size.width as f32;
+        let height = self.size.height as f32;
+
+        let stats = format!(
+            "{:.0} fps\n{:.2} ms",
+            self.stats.fps,
+            self.stats.frame_time.as_secs_f64() * 1000.0,
+        );
+        self.draw_text(&stats, [8.0, 8.0], &TextStyle::default());
+
+        let info = self.adapter.get_info();
+        let adapter = format!("{}\n{:?}", info.name, info.backend);
+        let style = TextStyle {
+            align: Align::Right,
+            max_width: Some(width - 16.0),
+            ..Default::default()
+        };
+        self.draw_text(&adapter, [8.0, 8.0], &style);
+
+        let style = TextStyle {
+            size: 14.0,
+            color: [0.7, 0.7, 0.7, 1.0],
+            align: Align::Center,
+            max_width: Some(width),
+            ..Default::default()
+        };
+        let hint = "press F1 to toggle the debug UI, Esc to quit";
+        let (_, hint_height) = self.text.measure(hint, &style);
+        self.draw_text(hint, [0.0, height - hint_height - 8.0], &style);
+
+        let assets = self.assets();
+        let settings = &mut self.settings;
+        self.ui
+            .run(&self.window, |ctx| debug_panel(ctx, settings, &assets));
+    }
+
+    /// Name and a short description of everything currently loaded.
+    fn assets(&self) -> Vec<(String, String)> {
+        let (width, height) = self.scene.diffuse.dimensions();
+        vec![
+            (
+                "texture.png".to_string(),
+                format!("{}x{} Rgba8UnormSrgb", width, height),
+            ),
+            (
+                "DejaVuSans.ttf".to_string(),
+                format!("{} glyphs cached", self.text.cached_glyphs()),
+            ),
+            (
+                "mesh".to_string(),
+                format!(
+                    "{} vertices, {} indices",
+                    self.scene.vertices.len(),
+                    self.scene.indices.len()
+                ),
+            ),
+        ]
+    }
 
     pub fn render(&mut self) -> Result<(), SurfaceError> {
+        // upload queued text first so it is consumed even if the frame is dropped
+        self.resources
+            .text
+            .prepare(&self.device, &self.queue, &mut self.text, self.size);
+        self.resources
+            .ui
+            .prepare(&self.device, &self.queue, &mut self.ui, self.size);
+
+        let aspect = self.size.width as f32 / self.size.height as f32;
+        self.queue.write_buffer(
+            &self.resources.camera_buffer,
+            0,
+            cast_slice(&[CameraUniform::new(&self.settings.camera, aspect)]),
+        );
+
         let surface_texture = self.surface.get_current_texture()?;
 
         let view = surface_texture
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        let [r, g, b] = self.settings.clear_color.map(|c| {
+            let c = c as f64;
+            if self.surface_config.format.is_srgb() {
+                // the picker works in sRGB, the clear value is linear
+                if c <= 0.04045 {
+                    c / 12.92
+                } else {
+                    ((c + 0.055) / 1.055).powf(2.4)
+                }
+            } else {
+                c
+            }
+        });
+        let clear_color = Color { r, g, b, a: 1.0 };
+
         // create command encoder
         let mut encoder = self
             .device
@@ -320,23 +754,42 @@ impl App {
                         view: &view,
                         resolve_target: None,
                         ops: Operations {
-                            load: LoadOp::Clear(Color {
-                                r: 0.0,
-                                g: 0.0,
-                                b: 0.0,
-                                a: 0.0,
-                            }),
+                            load: LoadOp::Clear(clear_color),
                             store: true,
                         },
                     }),
                 ],
                 depth_stencil_attachment: None,
             });
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+            let resources = &self.resources;
+            render_pass.set_pipeline(match self.settings.render_mode {
+                RenderMode::VertexColor => &resources.color_pipeline,
+                RenderMode::Texture => &resources.texture_pipeline,
+            });
+            render_pass.set_bind_group(0, &resources.texture_bind_group, &[]);
+            render_pass.set_bind_group(1, &resources.camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+            render_pass
+                .set_index_buffer(resources.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..resources.num_indices, 0, 0..1);
+
+            resources.text.draw(&mut render_pass);
+        }
+
+        {
+            let mut ui_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("UI Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            self.resources.ui.draw(&mut ui_pass);
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -345,3 +798,182 @@ impl App {
         Ok(())
     }
 }
+
+/// Either the adapter asked for on the command line or the best match for `surface`.
+async fn pick_adapter(
+    instance: &Instance,
+    surface: &wgpu::Surface,
+    options: &AdapterOptions,
+) -> Result<wgpu::Adapter, AppError> {
+    match &options.choice {
+        Some(choice) => {
+            let adapter = adapter::select(instance, options.backends, choice)
+                .ok_or_else(|| AppError::AdapterNotFound(choice.clone()))?;
+            if !adapter.is_surface_supported(surface) {
+                return Err(AppError::UnsupportedSurface {
+                    adapter: adapter.get_info(),
+                });
+            }
+            Ok(adapter)
+        }
+        None => instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::default(),
+                compatible_surface: Some(surface),
+                force_fallback_adapter: false, // don't fallback to software
+            })
+            .await
+            .ok_or(AppError::NoAdapter),
+    }
+}
+
+/// Request a device and install an error handler that logs validation errors
+/// and raises `device_lost` when the device can no longer be used.
+async fn request_device(
+    adapter: &wgpu::Adapter,
+    device_lost: &Arc<AtomicBool>,
+) -> Result<(wgpu::Device, wgpu::Queue), AppError> {
+    let (device, queue) = adapter
+        .request_device(
+            &DeviceDescriptor {
+                label: Some("Device"),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .map_err(|source| AppError::RequestDevice {
+            adapter: adapter.get_info(),
+            source,
+        })?;
+
+    let device_lost = device_lost.clone();
+    device.on_uncaptured_error(Box::new(move |error| match error {
+        // the allocation failed but the device is still usable, rebuilding
+        // every resource would only allocate more
+        wgpu::Error::OutOfMemory { source } => {
+            eprintln!("error: device ran out of memory: {}", source);
+        }
+        wgpu::Error::Validation {
+            source,
+            description,
+        } => {
+            // the description already carries the labels of the objects involved
+            eprintln!("validation error: {}", description);
+            if is_lost(source.as_ref()) {
+                device_lost.store(true, Ordering::Relaxed);
+            }
+        }
+    }));
+
+    Ok((device, queue))
+}
+
+/// Walk the error chain looking for wgpu-core's `DeviceError::Lost`.
+fn is_lost(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if let Some(DeviceError::Lost) = error.downcast_ref::<DeviceError>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// The debug window: scene settings, camera and loaded assets.
+fn debug_panel(ctx: &egui::Context, settings: &mut DebugSettings, assets: &[(String, String)]) {
+    egui::Window::new("Debug")
+        .default_pos([8.0, 64.0])
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::CollapsingHeader::new("Scene")
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Clear color");
+                        ui.color_edit_button_rgb(&mut settings.clear_color);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Render mode");
+                        egui::ComboBox::from_id_source("render_mode")
+                            .selected_text(settings.render_mode.label())
+                            .show_ui(ui, |ui| {
+                                for mode in RenderMode::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.render_mode,
+                                        mode,
+                                        mode.label(),
+                                    );
+                                }
+                            });
+                    });
+                });
+
+            egui::CollapsingHeader::new("Camera")
+                .default_open(true)
+                .show(ui, |ui| {
+                    let camera = &mut settings.camera;
+                    ui.horizontal(|ui| {
+                        ui.label("Position");
+                        ui.add(egui::DragValue::new(&mut camera.position[0]).speed(0.01));
+                        ui.add(egui::DragValue::new(&mut camera.position[1]).speed(0.01));
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut camera.zoom, 0.1..=10.0)
+                            .logarithmic(true)
+                            .text("Zoom"),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Rotation");
+                        ui.drag_angle(&mut camera.rotation);
+                    });
+                    ui.checkbox(&mut camera.keep_aspect, "Keep aspect ratio");
+                    if ui.button("Reset").clicked() {
+                        *camera = Camera::default();
+                    }
+                });
+
+            egui::CollapsingHeader::new("Assets").show(ui, |ui| {
+                egui::Grid::new("assets").striped(true).show(ui, |ui| {
+                    for (name, detail) in assets {
+                        ui.label(name);
+                        ui.label(detail);
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu_core::error::ContextError;
+
+    fn context(cause: impl Error + Send + Sync + 'static) -> ContextError {
+        ContextError {
+            string: "Queue::submit",
+            cause: Box::new(cause),
+            label_key: "",
+            label: String::new(),
+        }
+    }
+
+    #[test]
+    fn finds_device_loss_in_the_error_chain() {
+        assert!(is_lost(&context(DeviceError::Lost)));
+        assert!(!is_lost(&context(DeviceError::OutOfMemory)));
+
+        // the same message from another error is not a lost device
+        #[derive(Debug)]
+        struct Lookalike;
+        impl fmt::Display for Lookalike {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "Parent device is lost")
+            }
+        }
+        impl Error for Lookalike {}
+        assert!(!is_lost(&context(Lookalike)));
+    }
+}
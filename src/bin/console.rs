@@ -0,0 +1,111 @@
+//! Terminal front end: `console` runs the console engine demo, in the level
+//! file given with `console level <path>` or the built-in one. The demo
+//! takes key bindings from `--bindings <path>`, saves its input with
+//! `--record <path>` and plays a recording back with `--replay <path>`.
+//! `console snake` and `console blocks` play the sample games with the same
+//! options, `console guess` runs the number guessing game, `console scene`
+//! prints the wgpu scene without a GPU and `console image` prints an image
+//! file.
+
+use sketch::{
+    console_render::{self, BlitOptions, DemoOptions, Filter, Game, Palette, PixelMode},
+    guess_game,
+};
+
+const USAGE: &str = "\
+usage: console [guess]
+       console [level <path> | snake | blocks] [--bindings <path>] [--record <path> | --replay <path>]
+       console scene [ascii | blocks | braille]
+       console image <path> [ascii | blocks | braille] [16 | 256] [--dither] [--bilinear]";
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut options = DemoOptions {
+        bindings: take_flag(&mut args, "--bindings"),
+        record: take_flag(&mut args, "--record"),
+        replay: take_flag(&mut args, "--replay"),
+        ..DemoOptions::default()
+    };
+    if options.record.is_some() && options.replay.is_some() {
+        eprintln!(
+            "error: `--record` and `--replay` cannot be used together\n\n{}",
+            USAGE
+        );
+        std::process::exit(2);
+    }
+
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        None => console_render::console_render(&options),
+        Some("level") => match args.next() {
+            Some(path) => {
+                options.level = Some(path);
+                console_render::console_render(&options);
+            }
+            None => {
+                eprintln!("error: missing level path\n\n{}", USAGE);
+                std::process::exit(2);
+            }
+        },
+        Some("snake") => {
+            options.game = Game::Snake;
+            console_render::console_render(&options);
+        }
+        Some("blocks") => {
+            options.game = Game::Blocks;
+            console_render::console_render(&options);
+        }
+        Some("guess") => guess_game::run(),
+        Some("scene") => match args.next().as_deref() {
+            None | Some("ascii") => console_render::scene_preview(None),
+            Some("blocks") => console_render::scene_preview(Some(PixelMode::HalfBlock)),
+            Some("braille") => console_render::scene_preview(Some(PixelMode::Braille)),
+            Some(arg) => {
+                eprintln!("error: unknown shading `{}`\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        },
+        Some("image") => {
+            let Some(path) = args.next() else {
+                eprintln!("error: missing image path\n\n{}", USAGE);
+                std::process::exit(2);
+            };
+            let mut pixel_mode = None;
+            let mut options = BlitOptions::default();
+            for arg in args {
+                match arg.as_str() {
+                    "ascii" => pixel_mode = None,
+                    "blocks" => pixel_mode = Some(PixelMode::HalfBlock),
+                    "braille" => pixel_mode = Some(PixelMode::Braille),
+                    "16" => options.palette = Palette::Ansi16,
+                    "256" => options.palette = Palette::Ansi256,
+                    "--dither" => options.dither = true,
+                    "--bilinear" => options.filter = Filter::Bilinear,
+                    arg => {
+                        eprintln!("error: unknown argument `{}`\n\n{}", arg, USAGE);
+                        std::process::exit(2);
+                    }
+                }
+            }
+            if let Err(e) = console_render::image_preview(&path, pixel_mode, options) {
+                eprintln!("error: could not load `{}`: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        Some("-h" | "--help") => println!("{}", USAGE),
+        Some(arg) => {
+            eprintln!("error: unknown argument `{}`\n\n{}", arg, USAGE);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Remove `name <value>` from the arguments and return the value.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == name)?;
+    if i + 1 == args.len() {
+        eprintln!("error: missing path after `{}`\n\n{}", name, USAGE);
+        std::process::exit(2);
+    }
+    args.drain(i..i + 2).nth(1)
+}
@@ -0,0 +1,74 @@
+use bytemuck::{Pod, Zeroable};
+
+/// A 2D camera looking at the scene: pan, zoom and roll around the view center.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub position: [f32; 2],
+    pub zoom: f32,
+    /// Rotation in radians, counter-clockwise.
+    pub rotation: f32,
+    /// Squash the x axis by the aspect ratio so the scene is not stretched.
+    /// Off by default, which keeps the scene filling the window as before.
+    pub keep_aspect: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0],
+            zoom: 1.0,
+            rotation: 0.0,
+            keep_aspect: false,
+        }
+    }
+}
+
+impl Camera {
+    /// Column-major matrix taking scene positions to clip space for a target
+    /// of the given `aspect` (width / height).
+    pub fn view_proj(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let sx = if self.keep_aspect && aspect > 0.0 {
+            self.zoom / aspect
+        } else {
+            self.zoom
+        };
+        let sy = self.zoom;
+        let [px, py] = self.position;
+
+        // scale * rotate * translate(-position)
+        let tx = -(cos * px - sin * py);
+        let ty = -(sin * px + cos * py);
+        [
+            [sx * cos, sy * sin, 0.0, 0.0],
+            [-sx * sin, sy * cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [sx * tx, sy * ty, 0.0, 1.0],
+        ]
+    }
+
+    /// Where a scene position ends up in clip space, the transform the vertex
+    /// shader applies. The scene is flat, so `w` stays 1.
+    pub fn transform(&self, aspect: f32, position: [f32; 3]) -> [f32; 2] {
+        let m = self.view_proj(aspect);
+        let [x, y, z] = position;
+        [
+            m[0][0] * x + m[1][0] * y + m[2][0] * z + m[3][0],
+            m[0][1] * x + m[1][1] * y + m[2][1] * z + m[3][1],
+        ]
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+}
+
+impl CameraUniform {
+    pub fn new(camera: &Camera, aspect: f32) -> Self {
+        Self {
+            view_proj: camera.view_proj(aspect),
+        }
+    }
+}
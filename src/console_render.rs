@@ -1,216 +1,414 @@
-use std::{
-    arch::x86_64::_SIDD_LEAST_SIGNIFICANT,
-    collections::HashSet,
-    io::{self, stdin, BufWriter, Read, Write},
-    os::windows::thread,
-    process::Command,
-    sync::Mutex,
-    time::{Duration, Instant},
-    vec,
+//! A tiny game engine that renders to the terminal.
+
+use std::io;
+
+use crate::{
+    app::{INDICES, VERTICES},
+    camera::Camera,
+};
+
+mod actions;
+mod backend;
+mod blit;
+mod canvas;
+mod cell;
+mod demo;
+mod ecs;
+mod games;
+mod input;
+mod load;
+mod physics;
+mod pixels;
+mod raster;
+mod recording;
+mod scene;
+mod shapes;
+mod sprite;
+mod terminal;
+mod tilemap;
+mod timing;
+
+pub use actions::{Action, Actions, Bindings};
+pub use backend::{InputBackend, OutputBackend, TerminalInput, TerminalOutput, TestBackend};
+pub use blit::{BlitOptions, Filter, Palette};
+pub use canvas::{Canvas, DoubleBuffer};
+pub use cell::{Attrs, Cell, Color, Style};
+pub use ecs::{
+    Collider, Entity, Glyph, Position, PreviousPosition, Storage, System, Velocity, World,
 };
+pub use games::{Blocks, Snake};
+pub use input::{InputManager, Key, KeyState};
+pub use load::LoadError;
+pub use physics::{physics, Aabb, Body, Collision};
+pub use pixels::PixelMode;
+pub use raster::{draw_scene, rasterize, Shading};
+pub use recording::{InputEvent, Recording};
+pub use scene::{Scene, Transition};
+pub use sprite::{animate, Animation, Flip, Sprite, SpriteFrame};
+pub use terminal::RawMode;
+pub use tilemap::{follow_player, try_move, TileType, Tilemap, Viewport};
+pub use timing::{ManualTime, Time, Timestep};
 
-mod guess_game;
+use scene::SceneStack;
+use timing::Clock;
 
-struct DoubleBuffer {
-    front: Vec<char>,
-    back: Vec<char>,
-    current: usize,
+pub struct Engine {
+    canvas: Canvas,
+    output: Box<dyn OutputBackend>,
+    input: InputManager,
+    context: Context,
+    clock: Clock,
+    scenes: SceneStack,
+    systems: Vec<System>,
+    running: bool,
 }
 
-impl DoubleBuffer {
-    fn new(width: usize, height: usize) -> Self {
-        DoubleBuffer {
-            front: vec![' '; width * height],
-            back: vec![' '; width * height],
-            current: 0,
-        }
-    }
+/// State the engine shares with every scene.
+#[derive(Default)]
+pub struct Context {
+    /// Size of the canvas in cells, follows the terminal when it is resized,
+    /// or the recording during a replay.
+    pub width: usize,
+    pub height: usize,
+    pub time: Time,
+    /// Entities of the game, see [`World`].
+    pub world: World,
+    /// The entity the viewport follows.
+    pub player: Option<Entity>,
+    /// The level, drawn below the entities.
+    pub tilemap: Option<Tilemap>,
+    /// Which part of the level the canvas shows.
+    pub viewport: Viewport,
+    /// What collided during the last tick, see [`Collision`].
+    pub collisions: Vec<Collision>,
+}
 
-    fn swap(&mut self) {
-        self.current ^= 1;
+/// Cells the canvas border takes up on each axis.
+const BORDER: usize = 2;
+
+/// Canvas size that fills a screen, if its size is known.
+fn fit(size: Option<(usize, usize)>) -> Option<(usize, usize)> {
+    let (columns, rows) = size?;
+    Some((columns.saturating_sub(BORDER), rows.saturating_sub(BORDER)))
+}
+
+/// Canvas size that fills the terminal, if its size is known.
+fn fit_terminal() -> Option<(usize, usize)> {
+    fit(terminal::size())
+}
+
+impl Engine {
+    /// An engine reading keys from the terminal.
+    pub fn new(canvas: Canvas, context: Context) -> Self {
+        Self::with_input(canvas, context, InputManager::new())
     }
 
-    fn get_current_buffer(&self) -> &Vec<char> {
-        if self.current == 0 {
-            &self.front
-        } else {
-            &self.back
+    /// An engine taking keys from somewhere else, like
+    /// [`InputManager::replay`]. A replay that knows its canvas size resizes
+    /// the canvas to it.
+    pub fn with_input(mut canvas: Canvas, mut context: Context, mut input: InputManager) -> Self {
+        match input.size() {
+            Some((width, height)) if input.is_replay() => canvas.resize(width, height),
+            _ => input.set_size(canvas.width(), canvas.height()),
+        }
+        context.width = canvas.width();
+        context.height = canvas.height();
+        Engine {
+            canvas,
+            output: Box::new(TerminalOutput),
+            context,
+            input,
+            clock: Clock::new(Timestep::default(), None),
+            scenes: SceneStack::default(),
+            systems: vec![
+                Box::new(physics::physics),
+                Box::new(animate),
+                Box::new(follow_player),
+            ],
+            running: true,
         }
     }
 
-    fn get_current_buffer_mut(&mut self) -> &mut Vec<char> {
-        if self.current == 0 {
-            &mut self.front
-        } else {
-            &mut self.back
-        }
+    /// Run a system every tick, after the top scene updated, unless that
+    /// scene pauses them. Systems run in the order they were added, starting
+    /// with the built-in [`physics`], [`animate`] and [`follow_player`].
+    pub fn add_system(&mut self, system: impl FnMut(&mut Context) + 'static) {
+        self.systems.push(Box::new(system));
     }
 
-    fn clear(&mut self) {
-        let buffer = self.get_current_buffer_mut();
-        buffer.iter_mut().for_each(|c| *c = ' ');
+    /// Put a scene on top of the stack, it updates from the next tick on.
+    pub fn push(&mut self, scene: impl Scene + 'static) {
+        self.scenes.push(Box::new(scene), &mut self.context);
     }
-}
 
-/// A simple console renderer.
-struct Canvas {
-    width: usize,
-    height: usize,
-    buffer: DoubleBuffer,
-}
+    pub fn pop(&mut self) {
+        self.scenes.pop(&mut self.context);
+    }
 
-impl Canvas {
-    fn new(width: usize, height: usize) -> Self {
-        let border = 2;
-        Canvas {
-            width,
-            height,
-            buffer: DoubleBuffer::new(width + border, height + border),
-        }
+    pub fn replace(&mut self, scene: impl Scene + 'static) {
+        self.scenes.replace(Box::new(scene), &mut self.context);
     }
 
-    fn draw(&mut self, x: i32, y: i32, c: char) {
-        // border padding
-        let x = x + 1;
-        let y = y + 1;
+    /// Replace the key bindings scenes see through [`InputManager::actions`].
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.input.set_bindings(bindings);
+    }
 
-        let at = y * self.width as i32 + x;
-        if at < 0 || at >= (self.width * self.height) as i32 {
-            return;
-        }
-        let current_buffer = self.buffer.get_current_buffer_mut();
-        current_buffer[at as usize] = c;
-    }
-
-    fn render(&mut self) {
-        let mut stdout = BufWriter::new(io::stdout());
-        // clear the screen
-        write!(stdout, "\x1B[2J\x1B[1;1H").unwrap();
-        // disable cursor
-        write!(stdout, "\x1B[?25l").unwrap();
-
-        let curr_buffer = self.buffer.get_current_buffer();
-        for y in 0..=self.height {
-            for x in 0..=self.width {
-                if x == 0 || y == 0 || x == self.width || y == self.height {
-                    // border
-                    write!(stdout, "#").unwrap();
-                } else {
-                    let at = y * self.width + x;
-                    write!(stdout, "{}", curr_buffer[at]).unwrap();
-                }
-            }
-            write!(stdout, "\n").unwrap();
-        }
-        // self.buffer.swap();
-        self.buffer.clear(); // clear the back buffer
+    /// Send frames somewhere other than the terminal, like [`TestBackend`].
+    pub fn set_output(&mut self, output: impl OutputBackend + 'static) {
+        self.output = Box::new(output);
+        self.canvas.force_redraw();
+    }
 
-        stdout.flush().unwrap();
+    pub fn input_mut(&mut self) -> &mut InputManager {
+        &mut self.input
     }
-}
 
-struct Engine {
-    canvas: Canvas,
-    input: InputManager,
-    context: Context,
-}
+    pub fn set_timestep(&mut self, step: Timestep) {
+        self.clock = Clock::new(step, self.clock.manual_time());
+    }
 
-struct InputManager {
-    pressed_key: HashSet<char>,
-    input_receiver: std::sync::mpsc::Receiver<char>,
-}
+    /// Take time from `time` instead of the system clock, so ticks only run
+    /// as it is advanced and frames never sleep. Meant for tests.
+    pub fn set_manual_time(&mut self, time: ManualTime) {
+        self.clock = Clock::new(self.clock.timestep(), Some(time));
+    }
 
-impl InputManager {
-    fn new() -> Self {
-        let (tx, rx) = std::sync::mpsc::channel::<char>();
-        std::thread::spawn(move || loop {
-            let mut buf = [0; 1];
-            if stdin().read_exact(&mut buf).is_ok() {
-                for key in buf {
-                    tx.send(key as char).unwrap();
+    /// False once the last scene is gone, the player pressed Ctrl-C, a replay
+    /// ran out or the process got a termination signal.
+    pub fn is_running(&self) -> bool {
+        self.running && !self.scenes.is_empty()
+    }
+
+    /// Run one frame: the logic ticks that are due, then a render. Sleeps
+    /// out the rest of the frame when the frame rate is capped.
+    ///
+    /// Fails when the output cannot show the frame, which also stops the
+    /// engine.
+    pub fn run(&mut self) -> io::Result<()> {
+        let ticks = self.clock.begin_frame(&mut self.context.time);
+        for _ in 0..ticks {
+            // input advances per tick so presses line up with the logic
+            self.update_input();
+            if !self.running {
+                self.scenes.clear(&mut self.context);
+                return Ok(());
+            }
+            if let Some((width, height)) = self.input.size().filter(|_| self.input.is_replay()) {
+                self.resize_canvas(width, height);
+            }
+            ecs::remember_positions(&self.context.world);
+            self.scenes.update(&self.input, &mut self.context);
+            if self.scenes.runs_systems() {
+                for system in &mut self.systems {
+                    system(&mut self.context);
                 }
             }
-        });
-        InputManager {
-            pressed_key: HashSet::new(),
-            input_receiver: rx,
+            self.context.time.tick += 1;
+        }
+        if self.scenes.is_empty() {
+            return Ok(());
+        }
+        self.update_size();
+        if let Err(e) = self.update_render() {
+            self.running = false;
+            return Err(e);
         }
+        self.clock.end_frame();
+        Ok(())
     }
 
-    fn is_pressed(&self, key: char) -> bool {
-        self.pressed_key.contains(&key)
+    fn update_input(&mut self) {
+        if self.input.replay_finished() {
+            // nothing left to play back and no keyboard to quit with
+            self.running = false;
+            return;
+        }
+        self.input.update();
+        if self.input.just_pressed(Key::Ctrl('c')) || terminal::termination_requested() {
+            self.running = false;
+        }
     }
 
-    fn update(&mut self) {
-        while let Ok(key) = self.input_receiver.try_recv() {
-            self.pressed_key.insert(key);
+    /// Follow the terminal size, except in replays, where the recording
+    /// decides it.
+    fn update_size(&mut self) {
+        if !self.output.take_resized() {
+            return;
+        }
+        // the terminal may have dropped what was on it
+        self.canvas.force_redraw();
+        if self.input.is_replay() {
+            return;
+        }
+        if let Some((width, height)) = fit(self.output.size()) {
+            self.resize_canvas(width, height);
+            self.input.set_size(width, height);
         }
     }
 
-    fn clear(&mut self) {
-        self.pressed_key.clear();
+    fn resize_canvas(&mut self, width: usize, height: usize) {
+        if (width, height) != (self.canvas.width(), self.canvas.height()) {
+            self.canvas.resize(width, height);
+            self.context.width = width;
+            self.context.height = height;
+        }
+    }
+
+    fn update_render(&mut self) -> io::Result<()> {
+        let viewport = self.context.viewport;
+        if let Some(map) = &self.context.tilemap {
+            map.draw(&mut self.canvas, viewport);
+        }
+        let alpha = self.context.time.alpha;
+        ecs::draw_glyphs(&self.context.world, &mut self.canvas, viewport, alpha);
+        sprite::draw_animations(&self.context.world, &mut self.canvas, viewport, alpha);
+        self.scenes.draw(&mut self.canvas, &self.context);
+        self.canvas.render_to(self.output.as_mut())
     }
 }
 
-#[derive(Default)]
-struct Context {
-    player_x: i32,
-    player_y: i32,
+/// The level `console` starts in.
+const DEMO_LEVEL: &str = include_str!("../assets/levels/wander.txt");
+
+/// What `console` plays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Game {
+    /// Walk around a level, see [`DemoOptions::level`].
+    #[default]
+    Wander,
+    Snake,
+    Blocks,
 }
 
-impl Engine {
-    fn new(canvas: Canvas, context: Context) -> Self {
-        Engine {
-            canvas,
-            context,
-            input: InputManager::new(),
+/// The game to play and the files it loads and saves, all optional.
+#[derive(Clone, Debug, Default)]
+pub struct DemoOptions {
+    pub game: Game,
+    /// Level file for [`Game::Wander`] instead of the built-in level.
+    pub level: Option<String>,
+    /// Key bindings file instead of the default keys.
+    pub bindings: Option<String>,
+    /// Where to save the session's input when it ends.
+    pub record: Option<String>,
+    /// Recording to play back instead of reading the keyboard.
+    pub replay: Option<String>,
+}
+
+/// Run a game until it quits.
+pub fn console_render(options: &DemoOptions) {
+    let tilemap = match (options.game, &options.level) {
+        (Game::Wander, Some(path)) => Tilemap::load(path).map(Some),
+        (Game::Wander, None) => Tilemap::parse(DEMO_LEVEL).map(Some),
+        _ => Ok(None),
+    };
+    let tilemap = match tilemap {
+        Ok(tilemap) => tilemap,
+        Err(e) => {
+            let path = options.level.as_deref().unwrap_or("wander.txt");
+            eprintln!("error: could not load level `{}`: {}", path, e);
+            return;
         }
-    }
+    };
+    let bindings = match options.bindings.as_ref().map(Bindings::load).transpose() {
+        Ok(bindings) => bindings.unwrap_or_default(),
+        Err(e) => {
+            let path = options.bindings.as_deref().unwrap_or_default();
+            eprintln!("error: could not load bindings `{}`: {}", path, e);
+            return;
+        }
+    };
+    let mut input = match &options.replay {
+        Some(path) => match Recording::load(path) {
+            Ok(recording) => InputManager::replay(recording),
+            Err(e) => {
+                eprintln!("error: could not load recording `{}`: {}", path, e);
+                return;
+            }
+        },
+        None => InputManager::new(),
+    };
+    input.set_bindings(bindings);
 
-    fn run(&mut self) {
-        self.update_input();
-        self.update_logic();
-        self.update_render();
-        self.input.clear();
-    }
+    let raw_mode = match RawMode::enable() {
+        Ok(raw_mode) => raw_mode,
+        Err(e) => {
+            eprintln!("error: could not switch the terminal to raw mode: {e}");
+            return;
+        }
+    };
 
-    fn update_input(&mut self) {
-        self.input.update();
+    let (width, height) = fit_terminal().unwrap_or((20, 20));
+    let canvas = Canvas::new(width, height);
+    let context = Context {
+        tilemap,
+        ..Context::default()
+    };
+    let mut engine = Engine::with_input(canvas, context, input);
+    if options.record.is_some() {
+        // once the engine told the input the canvas size
+        engine.input_mut().start_recording();
+    }
+    match options.game {
+        Game::Wander => {
+            engine.add_system(demo::wrap_around);
+            engine.push(demo::Wander::default());
+        }
+        Game::Snake => engine.push(Snake::default()),
+        Game::Blocks => engine.push(Blocks::default()),
     }
 
-    fn update_render(&mut self) {
-        self.canvas
-            .draw(self.context.player_x, self.context.player_y, '@');
-        self.canvas.render();
+    let mut result = Ok(());
+    while result.is_ok() && engine.is_running() {
+        result = engine.run();
+    }
+    // back to the normal screen so errors stay visible
+    drop(raw_mode);
+    if let Err(e) = result {
+        eprintln!("error: could not draw to the terminal: {}", e);
     }
 
-    fn update_logic(&mut self) {
-        if self.input.is_pressed('w') {
-            self.context.player_y -= 1;
-        }
-        if self.input.is_pressed('s') {
-            self.context.player_y += 1;
-        }
-        if self.input.is_pressed('a') {
-            self.context.player_x -= 1;
-        }
-        if self.input.is_pressed('d') {
-            self.context.player_x += 1;
+    let recording = engine.input_mut().take_recording();
+    if let (Some(path), Some(recording)) = (&options.record, recording) {
+        if let Err(e) = recording.save(path) {
+            eprintln!("error: could not save recording `{}`: {}", path, e);
         }
     }
 }
 
-fn console_render() {
-    let canvas = Canvas::new(20, 20);
-    let context = Context::default();
-    let mut engine = Engine::new(canvas, context);
-
-    let fps = 30.0;
-    let frame_time = Duration::from_secs_f32(1.0 / fps);
+/// Print the wgpu scene once, drawn by the CPU rasterizer. `None` draws it
+/// with ASCII characters, otherwise it is plotted in the given pixel mode.
+pub fn scene_preview(pixel_mode: Option<PixelMode>) {
+    let (width, height) = fit_terminal().unwrap_or((60, 30));
+    let mut canvas = Canvas::new(width, height);
+    let shading = match pixel_mode {
+        Some(mode) => {
+            canvas.set_pixel_mode(mode);
+            Shading::Pixels
+        }
+        None => Shading::Ascii,
+    };
+    draw_scene(&mut canvas, VERTICES, INDICES, &Camera::default(), shading);
+    canvas.render();
+}
 
-    loop {
-        let start_time = Instant::now();
-        engine.run();
-        std::thread::sleep(start_time + frame_time - Instant::now());
-    }
+/// Print an image file once, scaled to fit the canvas.
+pub fn image_preview(
+    path: &str,
+    pixel_mode: Option<PixelMode>,
+    mut options: BlitOptions,
+) -> Result<(), image::ImageError> {
+    let image = image::open(path)?;
+    let (width, height) = fit_terminal().unwrap_or((60, 30));
+    let mut canvas = Canvas::new(width, height);
+    options.shading = match pixel_mode {
+        Some(mode) => {
+            canvas.set_pixel_mode(mode);
+            Shading::Pixels
+        }
+        None => Shading::Ascii,
+    };
+    canvas.blit(&image, 0, 0, width, height, &options);
+    canvas.render();
+    Ok(())
 }
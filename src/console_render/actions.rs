@@ -0,0 +1,231 @@
+//! Named game actions on top of raw keys, so games ask for `MoveUp` instead
+//! of `'w'` and players can rebind keys in a config file:
+//!
+//! ```text
+//! # action = key, key, ...
+//! move_up = w, k, Up
+//! confirm = Enter, Space
+//! quit = q, Ctrl-q
+//! ```
+//!
+//! Keys are single characters or `Space`, `Enter`, `Tab`, `Backspace`, `Esc`,
+//! `Up`, `Down`, `Left`, `Right`, `Home`, `End`, `PageUp`, `PageDown`,
+//! `Insert`, `Delete`, `F1`-`F12`, `Ctrl-<letter>` and `Alt-<char>`. Actions
+//! missing from the file keep their default keys.
+
+use std::{collections::HashMap, path::Path};
+
+use super::{
+    input::{InputManager, Key, KeyState},
+    load::{self, LoadError},
+};
+
+/// Something the player wants to do, independent of the key for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Confirm,
+    Cancel,
+    Pause,
+    Restart,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+    ];
+
+    /// The name used in binding files, e.g. `move_up`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// Which keys trigger which action. A key may be bound to several actions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Bindings {
+    /// No keys bound at all.
+    pub fn empty() -> Self {
+        Self {
+            keys: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        load::load(path, "bindings file", Self::parse)
+    }
+
+    /// Parse a bindings file on top of the defaults.
+    pub fn parse(text: &str) -> Result<Self, LoadError> {
+        let mut bindings = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |reason| LoadError::line(i, reason);
+            let (name, keys) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `action = key, ...`".to_owned()))?;
+            let action = Action::from_name(name.trim())
+                .ok_or_else(|| error(format!("unknown action `{}`", name.trim())))?;
+            let keys = keys
+                .split(',')
+                .map(|key| key.trim().parse().map_err(error))
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.keys.insert(action, keys);
+        }
+        Ok(bindings)
+    }
+
+    /// Add a key to an action, keeping the ones it has.
+    pub fn bind(&mut self, action: Action, key: impl Into<Key>) {
+        let key = key.into();
+        let keys = self.keys.entry(action).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    /// Remove every key from an action.
+    pub fn unbind(&mut self, action: Action) {
+        self.keys.remove(&action);
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl Default for Bindings {
+    /// WASD and the arrow keys to move, Enter or Space to confirm, Esc to
+    /// cancel, `p` to pause, `r` to restart and `q` to quit.
+    fn default() -> Self {
+        let mut bindings = Self::empty();
+        let defaults = [
+            (Action::MoveUp, [Key::Char('w'), Key::Up]),
+            (Action::MoveDown, [Key::Char('s'), Key::Down]),
+            (Action::MoveLeft, [Key::Char('a'), Key::Left]),
+            (Action::MoveRight, [Key::Char('d'), Key::Right]),
+            (Action::Confirm, [Key::Enter, Key::Char(' ')]),
+        ];
+        for (action, keys) in defaults {
+            for key in keys {
+                bindings.bind(action, key);
+            }
+        }
+        bindings.bind(Action::Cancel, Key::Esc);
+        bindings.bind(Action::Pause, 'p');
+        bindings.bind(Action::Restart, 'r');
+        bindings.bind(Action::Quit, 'q');
+        bindings
+    }
+}
+
+/// Action queries for one update, from [`InputManager::actions`]. An action
+/// is down while any of its keys is.
+pub struct Actions<'a> {
+    pub(super) input: &'a InputManager,
+}
+
+impl Actions<'_> {
+    /// Combined state of the action's keys: held if any key was already
+    /// down, pressed if one just went down, released once the last one
+    /// went up.
+    pub fn state(&self, action: Action) -> KeyState {
+        let states = self
+            .input
+            .bindings()
+            .keys(action)
+            .iter()
+            .map(|&key| self.input.state(key));
+        let mut state = KeyState::Up;
+        for key_state in states {
+            state = match (state, key_state) {
+                (_, KeyState::Held) | (KeyState::Held, _) => KeyState::Held,
+                (_, KeyState::Pressed) | (KeyState::Pressed, _) => KeyState::Pressed,
+                (_, KeyState::Released) | (KeyState::Released, _) => KeyState::Released,
+                _ => KeyState::Up,
+            };
+        }
+        state
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        matches!(self.state(action), KeyState::Pressed | KeyState::Held)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.state(action) == KeyState::Pressed
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.state(action) == KeyState::Held
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.state(action) == KeyState::Released
+    }
+
+    /// Whether any of the action's keys was pressed or auto-repeated, see
+    /// [`InputManager::is_triggered`].
+    pub fn is_triggered(&self, action: Action) -> bool {
+        self.input
+            .bindings()
+            .keys(action)
+            .iter()
+            .any(|&key| self.input.is_triggered(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_override_defaults_per_action() {
+        let bindings =
+            Bindings::parse("# vim keys\nmove_up = k, Up\nquit = Ctrl-q, F10\n").unwrap();
+        assert_eq!(bindings.keys(Action::MoveUp), [Key::Char('k'), Key::Up]);
+        assert_eq!(bindings.keys(Action::Quit), [Key::Ctrl('q'), Key::F(10)]);
+        assert_eq!(bindings.keys(Action::MoveDown), [Key::Char('s'), Key::Down]);
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        let error = Bindings::parse("confirm = Enter\njump = Space\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown action `jump`");
+        let error = Bindings::parse("confirm = Return\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: unknown key `Return`");
+    }
+}
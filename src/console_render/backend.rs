@@ -0,0 +1,278 @@
+//! Where frames go and keys come from. The engine talks to the terminal
+//! through these traits, so it can run against [`TestBackend`] instead.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, stdin, BufWriter, Read, Write},
+    rc::Rc,
+    sync::mpsc::{self, Receiver},
+};
+
+use super::{
+    canvas::Canvas,
+    input::{self, Key},
+    terminal,
+};
+
+/// Shows rendered frames.
+pub trait OutputBackend {
+    /// Show the canvas' current frame.
+    fn present(&mut self, canvas: &Canvas) -> io::Result<()>;
+
+    /// Size of the screen in cells, including the canvas border.
+    fn size(&self) -> Option<(usize, usize)>;
+
+    /// Whether the screen changed size since the last call.
+    fn take_resized(&mut self) -> bool;
+}
+
+/// Delivers key presses.
+pub trait InputBackend {
+    /// The keys that arrived since the last call, without waiting for more.
+    fn poll(&mut self) -> Vec<Key>;
+}
+
+/// Writes frames to stdout as escape sequences, sending only what changed.
+pub struct TerminalOutput;
+
+impl OutputBackend for TerminalOutput {
+    fn present(&mut self, canvas: &Canvas) -> io::Result<()> {
+        let mut stdout = BufWriter::new(io::stdout());
+        canvas.write_ansi(&mut stdout)?;
+        stdout.flush()
+    }
+
+    fn size(&self) -> Option<(usize, usize)> {
+        terminal::size()
+    }
+
+    fn take_resized(&mut self) -> bool {
+        terminal::take_resized()
+    }
+}
+
+/// Reads and decodes stdin on a background thread.
+pub struct TerminalInput {
+    receiver: Receiver<Key>,
+}
+
+impl TerminalInput {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel::<Key>();
+        std::thread::spawn(move || {
+            let mut stdin = stdin();
+            let mut buf = [0; 64];
+            loop {
+                let len = match stdin.read(&mut buf) {
+                    Ok(0) | Err(_) => return,
+                    Ok(len) => len,
+                };
+                for key in input::decode(&buf[..len]) {
+                    if tx.send(key).is_err() {
+                        // the engine is gone, nobody is listening anymore
+                        return;
+                    }
+                }
+            }
+        });
+        TerminalInput { receiver: rx }
+    }
+}
+
+impl Default for TerminalInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputBackend for TerminalInput {
+    fn poll(&mut self) -> Vec<Key> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+/// An in-memory screen and keyboard that records frames as text and plays
+/// scripted keys. Clones share the same state, so a test keeps one handle
+/// while the engine owns the others.
+#[derive(Clone, Default)]
+pub struct TestBackend {
+    state: Rc<RefCell<TestState>>,
+}
+
+#[derive(Default)]
+struct TestState {
+    size: (usize, usize),
+    resized: bool,
+    frames: Vec<String>,
+    /// Keys for each coming poll, one batch per poll.
+    keys: VecDeque<Vec<Key>>,
+}
+
+impl TestBackend {
+    /// A screen of the given size in cells, including the canvas border.
+    pub fn new(columns: usize, rows: usize) -> Self {
+        let backend = Self::default();
+        backend.state.borrow_mut().size = (columns, rows);
+        backend
+    }
+
+    /// Queue keys that arrive together at the next poll without keys queued.
+    pub fn push_keys<K: Into<Key>>(&self, keys: impl IntoIterator<Item = K>) {
+        let keys = keys.into_iter().map(Into::into).collect();
+        self.state.borrow_mut().keys.push_back(keys);
+    }
+
+    /// Queue a poll that returns no keys, e.g. to let a key be held.
+    pub fn push_idle(&self) {
+        self.state.borrow_mut().keys.push_back(Vec::new());
+    }
+
+    /// Whether every queued key was delivered.
+    pub fn keys_done(&self) -> bool {
+        self.state.borrow().keys.is_empty()
+    }
+
+    /// Change the screen size, the engine picks it up before the next frame.
+    pub fn resize(&self, columns: usize, rows: usize) {
+        let mut state = self.state.borrow_mut();
+        state.size = (columns, rows);
+        state.resized = true;
+    }
+
+    /// Every frame presented so far, see [`Canvas::to_text`].
+    pub fn frames(&self) -> Vec<String> {
+        self.state.borrow().frames.clone()
+    }
+
+    pub fn last_frame(&self) -> Option<String> {
+        self.state.borrow().frames.last().cloned()
+    }
+}
+
+impl OutputBackend for TestBackend {
+    fn present(&mut self, canvas: &Canvas) -> io::Result<()> {
+        self.state.borrow_mut().frames.push(canvas.to_text());
+        Ok(())
+    }
+
+    fn size(&self) -> Option<(usize, usize)> {
+        Some(self.state.borrow().size)
+    }
+
+    fn take_resized(&mut self) -> bool {
+        std::mem::take(&mut self.state.borrow_mut().resized)
+    }
+}
+
+impl InputBackend for TestBackend {
+    fn poll(&mut self) -> Vec<Key> {
+        self.state.borrow_mut().keys.pop_front().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::console_render::{
+        input::InputManager,
+        scene::{Scene, Transition},
+        Context, Engine, ManualTime, Timestep,
+    };
+
+    /// One tick at the default 30 ticks per second.
+    const TICK: Duration = Duration::from_nanos(1_000_000_000 / 30);
+
+    /// Counts presses of `d` until `q` is pressed.
+    struct Counter(u32);
+
+    impl Scene for Counter {
+        fn update(&mut self, input: &InputManager, _context: &mut Context) -> Transition {
+            if input.just_pressed('q') {
+                return Transition::Quit;
+            }
+            if input.is_triggered('d') {
+                self.0 += 1;
+            }
+            Transition::None
+        }
+
+        fn draw(&mut self, canvas: &mut Canvas, _context: &Context) {
+            canvas.text(0, 0, &format!("d {}", self.0), Default::default());
+        }
+    }
+
+    /// An engine running [`Counter`] on `backend`, its time moves along
+    /// with `time`.
+    fn engine(backend: &TestBackend, width: usize, height: usize, time: &ManualTime) -> Engine {
+        let input = InputManager::with_backend(backend.clone());
+        let mut engine = Engine::with_input(Canvas::new(width, height), Context::default(), input);
+        engine.set_output(backend.clone());
+        engine.set_timestep(Timestep {
+            max_fps: None,
+            ..Timestep::default()
+        });
+        engine.set_manual_time(time.clone());
+        engine.push(Counter(0));
+        engine
+    }
+
+    #[test]
+    fn runs_on_scripted_keys_without_a_terminal() {
+        let backend = TestBackend::new(6, 4);
+        let time = ManualTime::default();
+        let mut engine = engine(&backend, 4, 2, &time);
+        backend.push_keys(['d']);
+        backend.push_idle();
+        backend.push_keys(['d', 'q']);
+        // no time passed, so no tick and no keys read yet
+        engine.run().unwrap();
+        while engine.is_running() {
+            time.advance(TICK);
+            engine.run().unwrap();
+        }
+
+        assert!(backend.keys_done());
+        // `q` arrives with the second `d` and quits before it counts
+        assert_eq!(backend.frames(), ["d 0 \n    ", "d 1 \n    ", "d 1 \n    "]);
+    }
+
+    #[test]
+    fn follows_resizes() {
+        let backend = TestBackend::new(6, 4);
+        let mut engine = engine(&backend, 4, 2, &ManualTime::default());
+        engine.run().unwrap();
+        backend.resize(8, 3);
+        engine.run().unwrap();
+        assert_eq!(backend.last_frame().unwrap(), "d 0   ");
+    }
+
+    /// A screen that went away, like stdout piped into a closed reader.
+    struct Closed;
+
+    impl OutputBackend for Closed {
+        fn present(&mut self, _canvas: &Canvas) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn size(&self) -> Option<(usize, usize)> {
+            None
+        }
+
+        fn take_resized(&mut self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn output_errors_stop_the_engine() {
+        let backend = TestBackend::new(6, 4);
+        let mut engine = engine(&backend, 4, 2, &ManualTime::default());
+        engine.set_output(Closed);
+        let error = engine.run().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        assert!(!engine.is_running());
+    }
+}
@@ -0,0 +1,273 @@
+//! Drawing images onto the canvas: scaling, ASCII or pixel shading, and
+//! quantizing to the colors a terminal can show.
+
+use image::{DynamicImage, Rgba32FImage};
+
+use super::{
+    canvas::Canvas,
+    cell::{Color, Style},
+    raster::{luminance, Shading, RAMP},
+};
+
+/// Pixels more transparent than this are left out.
+const ALPHA_CUTOFF: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+/// The colors output is limited to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// The terminal's default foreground only.
+    Monochrome,
+    Ansi16,
+    Ansi256,
+    #[default]
+    Truecolor,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlitOptions {
+    pub filter: Filter,
+    pub shading: Shading,
+    pub palette: Palette,
+    /// Spread the quantization error with Floyd-Steinberg, only matters for
+    /// the 16 and 256 color palettes.
+    pub dither: bool,
+}
+
+impl Canvas {
+    /// Draw `image` scaled into the `width` by `height` cells at `x`, `y`.
+    /// With [`Shading::Pixels`] every cell holds as many pixels as the
+    /// canvas' pixel mode allows.
+    pub fn blit(
+        &mut self,
+        image: &DynamicImage,
+        x: i32,
+        y: i32,
+        width: usize,
+        height: usize,
+        options: &BlitOptions,
+    ) {
+        let (cell_w, cell_h) = match options.shading {
+            Shading::Ascii => (1, 1),
+            Shading::Pixels => self.pixel_mode().cell_size(),
+        };
+        let (w, h) = (width * cell_w, height * cell_h);
+        let mut pixels = scale(&image.to_rgba32f(), w, h, options.filter);
+        let colors = quantize(&mut pixels, w, options.palette, options.dither);
+
+        for (i, (pixel, color)) in pixels.iter().zip(colors).enumerate() {
+            if pixel[3] < ALPHA_CUTOFF {
+                continue;
+            }
+            let (px, py) = ((i % w) as i32, (i / w) as i32);
+            match options.shading {
+                Shading::Ascii => {
+                    let brightness = luminance([pixel[0], pixel[1], pixel[2]]).clamp(0.0, 1.0);
+                    let step = (brightness * (RAMP.len() - 1) as f32).round() as usize;
+                    let ch = RAMP[step.min(RAMP.len() - 1)];
+                    self.draw_styled(x + px, y + py, ch, Style::new().fg(color));
+                }
+                Shading::Pixels => {
+                    let (ox, oy) = (x * cell_w as i32, y * cell_h as i32);
+                    self.plot(ox + px, oy + py, color);
+                }
+            }
+        }
+    }
+}
+
+/// Resample to `width` by `height` sRGB pixels with straight alpha.
+fn scale(image: &Rgba32FImage, width: usize, height: usize, filter: Filter) -> Vec<[f32; 4]> {
+    let (src_w, src_h) = image.dimensions();
+    if src_w == 0 || src_h == 0 {
+        return vec![[0.0; 4]; width * height];
+    }
+    let texel = |x: i64, y: i64| {
+        let x = x.clamp(0, src_w as i64 - 1) as u32;
+        let y = y.clamp(0, src_h as i64 - 1) as u32;
+        image.get_pixel(x, y).0
+    };
+
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            // the source position of the target pixel's center
+            let u = (x as f32 + 0.5) * src_w as f32 / width as f32 - 0.5;
+            let v = (y as f32 + 0.5) * src_h as f32 / height as f32 - 0.5;
+            let pixel = match filter {
+                Filter::Nearest => texel(u.round() as i64, v.round() as i64),
+                Filter::Bilinear => {
+                    let (x0, y0) = (u.floor(), v.floor());
+                    let (fx, fy) = (u - x0, v - y0);
+                    let (x0, y0) = (x0 as i64, y0 as i64);
+                    let lerp = |a: [f32; 4], b: [f32; 4], t: f32| {
+                        [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+                    };
+                    let top = lerp(texel(x0, y0), texel(x0 + 1, y0), fx);
+                    let bottom = lerp(texel(x0, y0 + 1), texel(x0 + 1, y0 + 1), fx);
+                    lerp(top, bottom, fy)
+                }
+            };
+            out.push(pixel);
+        }
+    }
+    out
+}
+
+/// Pick a terminal color for every pixel. Dithering pushes each pixel's error
+/// onto its right and lower neighbours, so `pixels` is modified.
+fn quantize(pixels: &mut [[f32; 4]], width: usize, palette: Palette, dither: bool) -> Vec<Color> {
+    let entries = match palette {
+        Palette::Monochrome => return vec![Color::Default; pixels.len()],
+        Palette::Truecolor => {
+            return pixels
+                .iter()
+                .map(|p| {
+                    let [r, g, b] = [p[0], p[1], p[2]].map(to_byte);
+                    Color::Rgb(r, g, b)
+                })
+                .collect()
+        }
+        Palette::Ansi16 => 16,
+        Palette::Ansi256 => 256,
+    };
+
+    let mut colors = Vec::with_capacity(pixels.len());
+    for i in 0..pixels.len() {
+        let pixel = pixels[i];
+        let index = nearest(pixel, entries);
+        colors.push(if entries == 16 {
+            Color::Ansi(index)
+        } else {
+            Color::Indexed(index)
+        });
+
+        if !dither || pixel[3] < ALPHA_CUTOFF {
+            continue;
+        }
+        let chosen = palette_rgb(index);
+        let error = [0, 1, 2].map(|c| pixel[c] - chosen[c] as f32 / 255.0);
+        let (x, y, height) = (i % width, i / width, pixels.len() / width);
+        let mut spread = |dx: isize, dy: usize, weight: f32| {
+            let nx = x as isize + dx;
+            if nx < 0 || nx >= width as isize || y + dy >= height {
+                return;
+            }
+            let neighbour = &mut pixels[(y + dy) * width + nx as usize];
+            for c in 0..3 {
+                neighbour[c] += error[c] * weight;
+            }
+        };
+        spread(1, 0, 7.0 / 16.0);
+        spread(-1, 1, 3.0 / 16.0);
+        spread(0, 1, 5.0 / 16.0);
+        spread(1, 1, 1.0 / 16.0);
+    }
+    colors
+}
+
+/// The palette entry closest to `pixel` among the first `entries`.
+fn nearest(pixel: [f32; 4], entries: usize) -> u8 {
+    let target = [pixel[0], pixel[1], pixel[2]].map(|c| c.clamp(0.0, 1.0) * 255.0);
+    (0..entries)
+        .min_by_key(|&i| {
+            let rgb = palette_rgb(i as u8);
+            let distance: f32 = (0..3).map(|c| (rgb[c] as f32 - target[c]).powi(2)).sum();
+            distance as u32
+        })
+        .unwrap_or(0) as u8
+}
+
+/// RGB of a 256-color palette entry, xterm's defaults for the first 16.
+fn palette_rgb(index: u8) -> [u8; 3] {
+    const ANSI: [[u8; 3]; 16] = [
+        [0, 0, 0],
+        [205, 0, 0],
+        [0, 205, 0],
+        [205, 205, 0],
+        [0, 0, 238],
+        [205, 0, 205],
+        [0, 205, 205],
+        [229, 229, 229],
+        [127, 127, 127],
+        [255, 0, 0],
+        [0, 255, 0],
+        [255, 255, 0],
+        [92, 92, 255],
+        [255, 0, 255],
+        [0, 255, 255],
+        [255, 255, 255],
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match index {
+        0..=15 => ANSI[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            [
+                LEVELS[(i / 36) as usize],
+                LEVELS[(i / 6 % 6) as usize],
+                LEVELS[(i % 6) as usize],
+            ]
+        }
+        _ => [8 + 10 * (index - 232); 3],
+    }
+}
+
+fn to_byte(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn bilinear_blends_neighbours() {
+        let mut image = Rgba32FImage::new(2, 1);
+        image.put_pixel(1, 0, image::Rgba([1.0, 1.0, 1.0, 1.0]));
+        let pixels = scale(&image, 4, 1, Filter::Bilinear);
+        let reds: Vec<f32> = pixels.iter().map(|p| p[0]).collect();
+        assert_eq!(reds, [0.0, 0.25, 0.75, 1.0]);
+        let pixels = scale(&image, 4, 1, Filter::Nearest);
+        assert!(pixels.iter().all(|p| p[0] == 0.0 || p[0] == 1.0));
+    }
+
+    #[test]
+    fn palette_cube_and_grays() {
+        assert_eq!(palette_rgb(16), [0, 0, 0]);
+        assert_eq!(palette_rgb(196), [255, 0, 0]);
+        assert_eq!(palette_rgb(231), [255, 255, 255]);
+        assert_eq!(palette_rgb(244), [128, 128, 128]);
+    }
+
+    #[test]
+    fn dithering_mixes_colors() {
+        // a flat gray between two entries of the 16-color palette...
+        let gray = [0.3, 0.3, 0.3, 1.0];
+        let mut pixels = vec![gray; 16];
+        let plain = quantize(&mut pixels.clone(), 4, Palette::Ansi16, false);
+        assert!(plain.iter().all(|c| *c == plain[0]));
+        // ...comes out as a mix of entries once the error is spread
+        let dithered = quantize(&mut pixels, 4, Palette::Ansi16, true);
+        assert!(dithered.iter().any(|c| *c != dithered[0]));
+    }
+
+    #[test]
+    fn transparent_pixels_are_skipped() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([255, 255, 255, 255]));
+        let mut canvas = Canvas::new(2, 1);
+        let options = BlitOptions::default();
+        canvas.blit(&DynamicImage::ImageRgba8(image), 0, 0, 2, 1, &options);
+        assert_eq!(canvas.get(0, 0).unwrap().ch, '@');
+        assert_eq!(canvas.get(1, 0).unwrap().ch, ' ');
+    }
+}
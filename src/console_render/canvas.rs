@@ -0,0 +1,343 @@
+use std::io::{self, Write};
+
+use super::{
+    backend::{OutputBackend, TerminalOutput},
+    cell::{self, Cell, Style},
+    pixels::PixelMode,
+};
+
+pub struct DoubleBuffer {
+    front: Vec<Cell>,
+    back: Vec<Cell>,
+    current: usize,
+}
+
+impl DoubleBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        DoubleBuffer {
+            front: vec![Cell::EMPTY; width * height],
+            back: vec![Cell::EMPTY; width * height],
+            current: 0,
+        }
+    }
+
+    pub fn swap(&mut self) {
+        self.current ^= 1;
+    }
+
+    pub fn get_current_buffer(&self) -> &Vec<Cell> {
+        if self.current == 0 {
+            &self.front
+        } else {
+            &self.back
+        }
+    }
+
+    /// The buffer that was current before the last [`swap`](Self::swap).
+    pub fn get_previous_buffer(&self) -> &Vec<Cell> {
+        if self.current == 0 {
+            &self.back
+        } else {
+            &self.front
+        }
+    }
+
+    pub fn get_current_buffer_mut(&mut self) -> &mut Vec<Cell> {
+        if self.current == 0 {
+            &mut self.front
+        } else {
+            &mut self.back
+        }
+    }
+
+    pub fn clear(&mut self) {
+        let buffer = self.get_current_buffer_mut();
+        buffer.iter_mut().for_each(|c| *c = Cell::EMPTY);
+    }
+}
+
+/// A simple console renderer.
+///
+/// Frames are drawn into the current buffer while the previous one holds what
+/// is on screen, so `render` only has to send the cells that changed.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    buffer: DoubleBuffer,
+    /// The screen no longer matches the previous buffer, redraw everything.
+    full_redraw: bool,
+    /// How [`plot`](Self::plot) maps pixels onto cells.
+    pixel_mode: PixelMode,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            buffer: Self::allocate(width, height),
+            full_redraw: true,
+            pixel_mode: PixelMode::default(),
+        }
+    }
+
+    fn allocate(width: usize, height: usize) -> DoubleBuffer {
+        let border = 2;
+        DoubleBuffer::new(width + border, height + border)
+    }
+
+    /// Reallocate for a new size. The next `render` redraws the whole screen.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.buffer = Self::allocate(width, height);
+        self.full_redraw = true;
+    }
+
+    pub fn pixel_mode(&self) -> PixelMode {
+        self.pixel_mode
+    }
+
+    pub fn set_pixel_mode(&mut self, mode: PixelMode) {
+        self.pixel_mode = mode;
+    }
+
+    /// Make the next `render` redraw the whole screen, e.g. after something
+    /// else wrote to the terminal.
+    pub fn force_redraw(&mut self) {
+        self.full_redraw = true;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cell drawn at a position this frame, `None` outside the canvas.
+    pub fn get(&self, x: i32, y: i32) -> Option<&Cell> {
+        let at = self.index(x, y)?;
+        Some(&self.buffer.get_current_buffer()[at])
+    }
+
+    /// Cells per buffer row, the width plus the border on both sides.
+    fn stride(&self) -> usize {
+        self.width + 2
+    }
+
+    /// Buffer index of a canvas position, `None` if it falls outside.
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        // clip each axis on its own so nothing wraps into the next row
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        // skip the border row and column
+        Some((y as usize + 1) * self.stride() + x as usize + 1)
+    }
+
+    /// Draw a character in the terminal's default colors.
+    pub fn draw(&mut self, x: i32, y: i32, c: char) {
+        self.draw_cell(x, y, Cell::from(c));
+    }
+
+    pub fn draw_styled(&mut self, x: i32, y: i32, c: char, style: Style) {
+        self.draw_cell(x, y, Cell::new(c, style));
+    }
+
+    pub fn draw_cell(&mut self, x: i32, y: i32, cell: Cell) {
+        if let Some(at) = self.index(x, y) {
+            let current_buffer = self.buffer.get_current_buffer_mut();
+            current_buffer[at] = cell;
+        }
+    }
+
+    /// Show the frame on the terminal and start a new one.
+    pub fn render(&mut self) {
+        self.render_to(&mut TerminalOutput).unwrap();
+    }
+
+    /// Hand the frame to an output and start a new one.
+    pub fn render_to(&mut self, output: &mut dyn OutputBackend) -> io::Result<()> {
+        let result = output.present(self);
+        self.full_redraw = false;
+
+        // what was just drawn becomes the previous frame
+        self.buffer.swap();
+        self.buffer.clear();
+        result
+    }
+
+    /// Write the frame as terminal output: the whole screen after a resize or
+    /// [`force_redraw`](Self::force_redraw), otherwise what changed since the
+    /// last frame.
+    pub fn write_ansi(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.full_redraw {
+            self.render_full(out)
+        } else {
+            self.render_changes(out)
+        }
+    }
+
+    /// The frame as plain text without the border or styles, one line per
+    /// row.
+    pub fn to_text(&self) -> String {
+        let curr_buffer = self.buffer.get_current_buffer();
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for y in 1..=self.height {
+            let row = &curr_buffer[y * self.stride() + 1..][..self.width];
+            text.extend(row.iter().map(|cell| cell.ch));
+            if y != self.height {
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    fn render_full(&self, out: &mut impl Write) -> io::Result<()> {
+        // reset colors, clear the screen, disable cursor
+        write!(out, "\x1B[0m\x1B[2J\x1B[1;1H\x1B[?25l")?;
+
+        let curr_buffer = self.buffer.get_current_buffer();
+        let border = Cell::from('#');
+        let (right, bottom) = (self.width + 1, self.height + 1);
+        let mut pen = Style::default();
+        for y in 0..=bottom {
+            for x in 0..=right {
+                let cell = if x == 0 || y == 0 || x == right || y == bottom {
+                    &border
+                } else {
+                    &curr_buffer[y * self.stride() + x]
+                };
+                cell::write_sgr(out, pen, cell.style())?;
+                pen = cell.style();
+                write!(out, "{}", cell.ch)?;
+            }
+            // a newline after the last row would scroll a canvas that fills
+            // the terminal, raw mode keeps output processing so it is \r\n
+            if y != bottom {
+                writeln!(out)?;
+            }
+        }
+        cell::write_sgr(out, pen, Style::default())
+    }
+
+    /// Write the cells that differ from the previous frame, moving the cursor
+    /// only where a run of changed cells starts and changing colors and
+    /// attributes only where they differ from the cell before.
+    fn render_changes(&self, out: &mut impl Write) -> io::Result<()> {
+        let curr_buffer = self.buffer.get_current_buffer();
+        let prev_buffer = self.buffer.get_previous_buffer();
+        // where the terminal cursor is after the last write
+        let mut cursor = None;
+        let mut pen = Style::default();
+
+        for y in 1..=self.height {
+            for x in 1..=self.width {
+                let at = y * self.stride() + x;
+                if curr_buffer[at] == prev_buffer[at] {
+                    continue;
+                }
+                if cursor != Some((x, y)) {
+                    // 1-based row;column
+                    write!(out, "\x1B[{};{}H", y + 1, x + 1)?;
+                }
+                let cell = &curr_buffer[at];
+                cell::write_sgr(out, pen, cell.style())?;
+                pen = cell.style();
+                write!(out, "{}", cell.ch)?;
+                cursor = Some((x + 1, y));
+            }
+        }
+        // leave the terminal in default colors between frames
+        cell::write_sgr(out, pen, Style::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console_render::cell::Color;
+
+    fn rows(canvas: &Canvas) -> Vec<String> {
+        let mut out = Vec::new();
+        canvas.render_full(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let frame = out
+            .strip_prefix("\x1B[0m\x1B[2J\x1B[1;1H\x1B[?25l")
+            .unwrap();
+        frame.lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn draws_inside_the_border() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.draw(0, 0, 'a');
+        canvas.draw(3, 0, 'b');
+        canvas.draw(0, 2, 'c');
+        canvas.draw(3, 2, 'd');
+        assert_eq!(
+            rows(&canvas),
+            ["######", "#a  b#", "#    #", "#c  d#", "######"]
+        );
+    }
+
+    #[test]
+    fn clips_each_axis() {
+        let mut canvas = Canvas::new(4, 3);
+        // one past the right edge must not wrap into the next row
+        canvas.draw(4, 0, 'x');
+        canvas.draw(0, 3, 'x');
+        canvas.draw(100, 1, 'x');
+        assert_eq!(
+            rows(&canvas),
+            ["######", "#    #", "#    #", "#    #", "######"]
+        );
+    }
+
+    #[test]
+    fn clips_negative_coordinates() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.draw(-1, 0, 'x');
+        canvas.draw(0, -1, 'x');
+        canvas.draw(-1, -1, 'x');
+        canvas.draw(i32::MIN, 1, 'x');
+        assert_eq!(canvas.index(-1, 1), None);
+        assert!(rows(&canvas).iter().all(|row| !row.contains('x')));
+    }
+
+    #[test]
+    fn styles_change_only_between_runs() {
+        let mut canvas = Canvas::new(4, 1);
+        let red = Style::new().fg(Color::RED);
+        canvas.draw_styled(0, 0, 'a', red);
+        canvas.draw_styled(1, 0, 'b', red);
+        canvas.draw_styled(2, 0, 'c', red.bold());
+        assert_eq!(rows(&canvas)[1], "#\x1B[31mab\x1B[1mc\x1B[0m #");
+    }
+
+    #[test]
+    fn index_is_stride_aware() {
+        let canvas = Canvas::new(4, 3);
+        assert_eq!(canvas.index(0, 0), Some(7));
+        assert_eq!(canvas.index(3, 0), Some(10));
+        assert_eq!(canvas.index(0, 1), Some(13));
+        assert_eq!(canvas.index(3, 2), Some(22));
+        assert_eq!(canvas.index(4, 2), None);
+    }
+
+    #[test]
+    fn only_changed_cells_are_sent() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.draw(1, 1, '@');
+        canvas.render_full(&mut Vec::new()).unwrap();
+        canvas.buffer.swap();
+        canvas.buffer.clear();
+
+        canvas.draw(2, 1, '@');
+        let mut out = Vec::new();
+        canvas.render_changes(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1B[3;3H @");
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    io::{self, Write},
+    ops::BitOr,
+};
+
+/// A terminal color, from the 16 ANSI colors up to 24-bit truecolor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// Whatever the terminal uses when nothing is set.
+    #[default]
+    Default,
+    /// One of the 16 ANSI colors, 0-7 normal and 8-15 bright.
+    Ansi(u8),
+    /// An entry of the 256-color palette.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    pub const BLACK: Color = Color::Ansi(0);
+    pub const RED: Color = Color::Ansi(1);
+    pub const GREEN: Color = Color::Ansi(2);
+    pub const YELLOW: Color = Color::Ansi(3);
+    pub const BLUE: Color = Color::Ansi(4);
+    pub const MAGENTA: Color = Color::Ansi(5);
+    pub const CYAN: Color = Color::Ansi(6);
+    pub const WHITE: Color = Color::Ansi(7);
+    pub const BRIGHT_BLACK: Color = Color::Ansi(8);
+    pub const BRIGHT_RED: Color = Color::Ansi(9);
+    pub const BRIGHT_GREEN: Color = Color::Ansi(10);
+    pub const BRIGHT_YELLOW: Color = Color::Ansi(11);
+    pub const BRIGHT_BLUE: Color = Color::Ansi(12);
+    pub const BRIGHT_MAGENTA: Color = Color::Ansi(13);
+    pub const BRIGHT_CYAN: Color = Color::Ansi(14);
+    pub const BRIGHT_WHITE: Color = Color::Ansi(15);
+
+    /// SGR parameters selecting this color, `base` is 30 for the foreground
+    /// and 40 for the background.
+    fn sgr(self, base: u8) -> String {
+        match self {
+            Color::Default => format!("{}", base + 9),
+            Color::Ansi(n) if n < 8 => format!("{}", base + n),
+            Color::Ansi(n) => format!("{}", base + 60 + (n & 7)),
+            Color::Indexed(n) => format!("{};5;{n}", base + 8),
+            Color::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", base + 8),
+        }
+    }
+}
+
+/// Text attributes, combined with `|`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Attrs(u8);
+
+impl Attrs {
+    pub const NONE: Attrs = Attrs(0);
+    pub const BOLD: Attrs = Attrs(1);
+    pub const UNDERLINE: Attrs = Attrs(1 << 1);
+    pub const REVERSE: Attrs = Attrs(1 << 2);
+
+    /// Each attribute with the SGR parameter that turns it on.
+    const CODES: [(Attrs, u8); 3] = [(Attrs::BOLD, 1), (Attrs::UNDERLINE, 4), (Attrs::REVERSE, 7)];
+
+    pub fn contains(self, other: Attrs) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Attrs {
+    type Output = Attrs;
+
+    fn bitor(self, rhs: Attrs) -> Attrs {
+        Attrs(self.0 | rhs.0)
+    }
+}
+
+/// Colors and attributes of a cell, without the character.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attrs,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fg(mut self, color: Color) -> Self {
+        self.fg = color;
+        self
+    }
+
+    pub fn bg(mut self, color: Color) -> Self {
+        self.bg = color;
+        self
+    }
+
+    pub fn attrs(mut self, attrs: Attrs) -> Self {
+        self.attrs = self.attrs | attrs;
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.attrs(Attrs::BOLD)
+    }
+
+    pub fn underline(self) -> Self {
+        self.attrs(Attrs::UNDERLINE)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.attrs(Attrs::REVERSE)
+    }
+}
+
+/// One character cell of the canvas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attrs,
+}
+
+impl Cell {
+    pub const EMPTY: Cell = Cell {
+        ch: ' ',
+        fg: Color::Default,
+        bg: Color::Default,
+        attrs: Attrs::NONE,
+    };
+
+    pub fn new(ch: char, style: Style) -> Self {
+        Cell {
+            ch,
+            fg: style.fg,
+            bg: style.bg,
+            attrs: style.attrs,
+        }
+    }
+
+    pub fn style(&self) -> Style {
+        Style {
+            fg: self.fg,
+            bg: self.bg,
+            attrs: self.attrs,
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::EMPTY
+    }
+}
+
+impl From<char> for Cell {
+    fn from(ch: char) -> Self {
+        Cell::new(ch, Style::default())
+    }
+}
+
+/// Write the SGR sequence that takes the terminal from style `from` to `to`,
+/// nothing if they match. Attributes can only be turned off one by one with
+/// codes some terminals lack, so dropping any of them resets everything.
+pub fn write_sgr(out: &mut impl Write, from: Style, to: Style) -> io::Result<()> {
+    if from == to {
+        return Ok(());
+    }
+
+    let mut params = Vec::new();
+    let from = if !to.attrs.contains(from.attrs) {
+        params.push("0".to_owned());
+        Style::default()
+    } else {
+        from
+    };
+
+    for (attr, code) in Attrs::CODES {
+        if to.attrs.contains(attr) && !from.attrs.contains(attr) {
+            params.push(code.to_string());
+        }
+    }
+    if to.fg != from.fg {
+        params.push(to.fg.sgr(30));
+    }
+    if to.bg != from.bg {
+        params.push(to.bg.sgr(40));
+    }
+
+    if params.is_empty() {
+        return Ok(());
+    }
+    write!(out, "\x1B[{}m", params.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sgr(from: Style, to: Style) -> String {
+        let mut out = Vec::new();
+        write_sgr(&mut out, from, to).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn only_changes_are_sent() {
+        let red = Style::new().fg(Color::RED);
+        assert_eq!(sgr(red, red), "");
+        assert_eq!(sgr(Style::new(), red), "\x1B[31m");
+        assert_eq!(sgr(red, red.bold()), "\x1B[1m");
+        assert_eq!(sgr(red, red.bg(Color::BRIGHT_BLUE)), "\x1B[104m");
+    }
+
+    #[test]
+    fn color_depths() {
+        let new = Style::new();
+        assert_eq!(sgr(new, new.fg(Color::Indexed(208))), "\x1B[38;5;208m");
+        assert_eq!(sgr(new, new.bg(Color::Rgb(1, 2, 3))), "\x1B[48;2;1;2;3m");
+        assert_eq!(sgr(new.fg(Color::RED), new), "\x1B[39m");
+    }
+
+    #[test]
+    fn dropping_an_attribute_resets() {
+        let style = Style::new().fg(Color::GREEN).bold().underline();
+        assert_eq!(
+            sgr(style, Style::new().fg(Color::GREEN).underline()),
+            "\x1B[0;4;32m"
+        );
+    }
+}
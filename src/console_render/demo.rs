@@ -0,0 +1,222 @@
+//! The scenes `console` runs when started without arguments.
+
+use std::rc::Rc;
+
+use super::{
+    actions::Action,
+    canvas::Canvas,
+    cell::{Cell, Color, Style},
+    ecs::{Collider, Entity, Glyph, Position, PreviousPosition, Velocity},
+    input::InputManager,
+    physics::Collision,
+    scene::{Scene, Transition},
+    sprite::{Animation, Flip, Sprite},
+    tilemap::try_move,
+    Context,
+};
+
+/// The bird flying over the level, facing right.
+const BIRD: &str = include_str!("../../assets/sprites/bird.txt");
+
+/// Walk an `@` around the level and catch the stars drifting by while birds
+/// fly over. With the default bindings WASD or the arrow keys move, `p`
+/// pauses, `q` quits.
+#[derive(Default)]
+pub struct Wander {
+    player: Option<Entity>,
+    stars: Vec<Entity>,
+    birds: Vec<Entity>,
+    caught: u32,
+}
+
+const TILE: Collider = Collider {
+    width: 1.0,
+    height: 1.0,
+};
+
+impl Scene for Wander {
+    fn on_enter(&mut self, context: &mut Context) {
+        let (spawn_x, spawn_y) = context
+            .tilemap
+            .as_ref()
+            .and_then(|map| map.spawn())
+            .unwrap_or((0, 0));
+        let (width, height) = bounds(context);
+
+        let world = &mut context.world;
+        let player = world.spawn();
+        world.insert(
+            player,
+            Position {
+                x: spawn_x as f32,
+                y: spawn_y as f32,
+            },
+        );
+        world.insert(
+            player,
+            Glyph(Cell::new('@', Style::new().fg(Color::BRIGHT_YELLOW).bold())),
+        );
+        world.insert(player, TILE);
+        self.player = Some(player);
+        context.player = Some(player);
+
+        for i in 0..8 {
+            let star = world.spawn();
+            let x = (i * 7 % 20) as f32 * width / 20.0;
+            let y = (i * 3 % 10) as f32 * height / 10.0;
+            world.insert(star, Position { x, y });
+            world.insert(
+                star,
+                Velocity {
+                    x: -2.0 - i as f32,
+                    y: 0.0,
+                },
+            );
+            world.insert(star, Glyph(Cell::new('*', Style::new().fg(Color::BLUE))));
+            world.insert(star, TILE);
+            self.stars.push(star);
+        }
+
+        let bird = Rc::new(Sprite::parse(BIRD).expect("the bird sprite parses"));
+        for i in 0..2 {
+            let entity = world.spawn();
+            let position = Position {
+                x: width * (0.3 + 0.5 * i as f32),
+                y: 1.0 + i as f32 * height / 2.0,
+            };
+            world.insert(entity, position);
+            world.insert(entity, Velocity { x: -6.0, y: 0.0 });
+            // flying left
+            let mut animation = Animation::new(bird.clone());
+            animation.flip = Flip::HORIZONTAL;
+            world.insert(entity, animation);
+            self.birds.push(entity);
+        }
+    }
+
+    fn update(&mut self, input: &InputManager, context: &mut Context) -> Transition {
+        let actions = input.actions();
+        if actions.just_pressed(Action::Quit) {
+            return Transition::Quit;
+        }
+        if actions.just_pressed(Action::Pause) {
+            return Transition::Push(Box::new(Pause));
+        }
+
+        let Some(player) = self.player else {
+            return Transition::None;
+        };
+        // a caught star starts over at the right edge
+        let (width, _) = bounds(context);
+        for collision in &context.collisions {
+            let Collision::Overlap(a, b) = *collision else {
+                continue;
+            };
+            let star = if a == player { b } else { a };
+            if (a == player || b == player) && self.stars.contains(&star) {
+                self.caught += 1;
+                if let Some(position) = context.world.write::<Position>().get_mut(star) {
+                    position.x = width - 1.0;
+                }
+            }
+        }
+
+        // one step per keystroke, auto-repeat included
+        if actions.is_triggered(Action::MoveUp) {
+            try_move(context, player, 0, -1);
+        }
+        if actions.is_triggered(Action::MoveDown) {
+            try_move(context, player, 0, 1);
+        }
+        if actions.is_triggered(Action::MoveLeft) {
+            try_move(context, player, -1, 0);
+        }
+        if actions.is_triggered(Action::MoveRight) {
+            try_move(context, player, 1, 0);
+        }
+        Transition::None
+    }
+
+    fn draw(&mut self, canvas: &mut Canvas, context: &Context) {
+        let time = &context.time;
+        canvas.text(
+            0,
+            context.height as i32 - 1,
+            &format!(
+                "{} caught {:.0} fps {:.0} ticks/s",
+                self.caught, time.fps, time.tick_rate
+            ),
+            Style::new().fg(Color::BRIGHT_BLACK),
+        );
+    }
+
+    fn on_exit(&mut self, context: &mut Context) {
+        context.player = None;
+        let entities = self.player.take().into_iter();
+        for entity in entities
+            .chain(self.stars.drain(..))
+            .chain(self.birds.drain(..))
+        {
+            context.world.despawn(entity);
+        }
+    }
+}
+
+/// The area stars drift through, the level or else the canvas.
+fn bounds(context: &Context) -> (f32, f32) {
+    let (width, height) = match &context.tilemap {
+        Some(map) => (map.width(), map.height()),
+        None => (context.width, context.height),
+    };
+    (width.max(1) as f32, height.max(1) as f32)
+}
+
+/// Wrap stars and birds that leave the level around to the other side. The
+/// previous position wraps along, so they don't streak across the screen.
+pub fn wrap_around(context: &mut Context) {
+    let (width, height) = bounds(context);
+    let velocities = context.world.read::<Velocity>();
+    let mut positions = context.world.write::<Position>();
+    let mut previous = context.world.write::<PreviousPosition>();
+    for (entity, _) in velocities.iter() {
+        if let Some(position) = positions.get_mut(entity) {
+            let (x, y) = (position.x.rem_euclid(width), position.y.rem_euclid(height));
+            if let Some(PreviousPosition(previous)) = previous.get_mut(entity) {
+                previous.x += x - position.x;
+                previous.y += y - position.y;
+            }
+            (position.x, position.y) = (x, y);
+        }
+    }
+}
+
+/// Drawn over the scene below it until pause or cancel is pressed.
+pub struct Pause;
+
+impl Scene for Pause {
+    fn update(&mut self, input: &InputManager, _context: &mut Context) -> Transition {
+        let actions = input.actions();
+        if actions.just_pressed(Action::Pause) || actions.just_pressed(Action::Cancel) {
+            return Transition::Pop;
+        }
+        if actions.just_pressed(Action::Quit) {
+            return Transition::Quit;
+        }
+        Transition::None
+    }
+
+    fn draw(&mut self, canvas: &mut Canvas, context: &Context) {
+        let text = " PAUSED ";
+        let x = (context.width as i32 - text.len() as i32) / 2;
+        let y = context.height as i32 / 2;
+        canvas.text(x, y, text, Style::new().reverse().bold());
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn runs_systems(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,388 @@
+//! A small entity-component store. Entities are ids, components live in one
+//! storage per type, and systems are functions over the [`Context`] the
+//! engine runs every tick.
+
+use std::{
+    any::{Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+};
+
+use super::{
+    canvas::Canvas, cell::Cell, physics::Body, sprite::Animation, tilemap::Viewport, Context,
+};
+
+/// An id for a game object. Ids of despawned entities are reused with a new
+/// generation, so stale ones never match the entity that took their place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+/// Components of one type, indexed by entity.
+pub struct Storage<T> {
+    slots: Vec<Option<(u32, T)>>,
+}
+
+impl<T> Storage<T> {
+    fn new() -> Self {
+        Self { slots: Vec::new() }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let (generation, component) = slot.as_ref()?;
+            let entity = Entity {
+                index: index as u32,
+                generation: *generation,
+            };
+            Some((entity, component))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let (generation, component) = slot.as_mut()?;
+                let entity = Entity {
+                    index: index as u32,
+                    generation: *generation,
+                };
+                Some((entity, component))
+            })
+    }
+
+    fn insert(&mut self, entity: Entity, component: T) {
+        let index = entity.index as usize;
+        if self.slots.len() <= index {
+            self.slots.resize_with(index + 1, || None);
+        }
+        self.slots[index] = Some((entity.generation, component));
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slots.get_mut(entity.index as usize)?;
+        match slot {
+            Some((generation, _)) if *generation == entity.generation => {
+                slot.take().map(|(_, component)| component)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Lets the world drop components of any type when an entity despawns.
+trait AnyStorage {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// All entities and their components. Storages are borrowed per type, so a
+/// system can write positions while reading velocities.
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, RefCell<Box<dyn AnyStorage>>>,
+}
+
+impl World {
+    /// An empty world with the built-in components registered.
+    pub fn new() -> Self {
+        let mut world = Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            storages: HashMap::new(),
+        };
+        world.register::<Position>();
+        world.register::<PreviousPosition>();
+        world.register::<Velocity>();
+        world.register::<Glyph>();
+        world.register::<Collider>();
+        world.register::<Body>();
+        world.register::<Animation>();
+        world
+    }
+
+    /// Create the storage for a component type so it can be read before
+    /// anything has it. Inserting registers the type too.
+    pub fn register<T: 'static>(&mut self) {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(Storage::<T>::new())));
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Remove an entity with all its components. Stale ids are ignored.
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_entity(entity);
+        }
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free.push(entity.index);
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index) == Some(&true) && self.generations[index] == entity.generation
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(index, _)| Entity {
+                index: index as u32,
+                generation: self.generations[index],
+            })
+    }
+
+    /// Add or replace a component. Ignored for despawned entities.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        self.register::<T>();
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .unwrap()
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap()
+            .insert(entity, component);
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .unwrap()
+            .remove(entity)
+    }
+
+    /// Borrow the storage of one component type.
+    ///
+    /// # Panics
+    ///
+    /// If the type is not registered or is borrowed mutably at the same time.
+    pub fn read<T: 'static>(&self) -> Ref<'_, Storage<T>> {
+        Ref::map(self.storage::<T>().borrow(), |storage| {
+            storage.as_any().downcast_ref::<Storage<T>>().unwrap()
+        })
+    }
+
+    /// Borrow the storage of one component type mutably.
+    ///
+    /// # Panics
+    ///
+    /// If the type is not registered or is already borrowed.
+    pub fn write<T: 'static>(&self) -> RefMut<'_, Storage<T>> {
+        RefMut::map(self.storage::<T>().borrow_mut(), |storage| {
+            storage.as_any_mut().downcast_mut::<Storage<T>>().unwrap()
+        })
+    }
+
+    /// Clone of an entity's component, handy for small `Copy` components.
+    pub fn get<T: Clone + 'static>(&self, entity: Entity) -> Option<T> {
+        self.read::<T>().get(entity).cloned()
+    }
+
+    fn storage<T: 'static>(&self) -> &RefCell<Box<dyn AnyStorage>> {
+        match self.storages.get(&TypeId::of::<T>()) {
+            Some(storage) => storage,
+            None => panic!(
+                "component `{}` is not registered",
+                std::any::type_name::<T>()
+            ),
+        }
+    }
+}
+
+/// Where an entity is, in canvas cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Where an entity was when the current tick started. The engine keeps it
+/// for every entity with a [`Position`], so drawing can blend between ticks.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PreviousPosition(pub Position);
+
+impl Position {
+    /// Where to draw an entity `alpha` of the way through the tick, see
+    /// [`Time::alpha`](super::Time::alpha). Without a previous position it
+    /// stays where it is.
+    pub fn interpolate(self, previous: Option<&PreviousPosition>, alpha: f32) -> Position {
+        let Some(PreviousPosition(previous)) = previous else {
+            return self;
+        };
+        Position {
+            x: previous.x + (self.x - previous.x) * alpha,
+            y: previous.y + (self.y - previous.y) * alpha,
+        }
+    }
+}
+
+/// Movement in cells per second.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Velocity {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// How an entity looks, one cell at its position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyph(pub Cell);
+
+/// An axis-aligned box starting at the entity's position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collider {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A system, run by the engine once per logic tick.
+pub type System = Box<dyn FnMut(&mut Context)>;
+
+/// Save every position as the entity's [`PreviousPosition`], the engine does
+/// this before each tick.
+pub(crate) fn remember_positions(world: &World) {
+    let mut previous = world.write::<PreviousPosition>();
+    for (entity, position) in world.read::<Position>().iter() {
+        previous.insert(entity, PreviousPosition(*position));
+    }
+}
+
+/// Draw every entity that has a position and a glyph, as seen through the
+/// viewport, `alpha` of the way through the tick.
+pub fn draw_glyphs(world: &World, canvas: &mut Canvas, viewport: Viewport, alpha: f32) {
+    let positions = world.read::<Position>();
+    let previous = world.read::<PreviousPosition>();
+    for (entity, Glyph(cell)) in world.read::<Glyph>().iter() {
+        if let Some(position) = positions.get(entity) {
+            let position = position.interpolate(previous.get(entity), alpha);
+            let x = position.x.round() as i32 - viewport.x;
+            let y = position.y.round() as i32 - viewport.y;
+            canvas.draw_cell(x, y, *cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_ids_do_not_match_reused_slots() {
+        let mut world = World::new();
+        let a = world.spawn();
+        world.insert(a, Position { x: 1.0, y: 2.0 });
+        world.despawn(a);
+
+        let b = world.spawn();
+        assert!(!world.is_alive(a));
+        assert!(world.is_alive(b));
+        assert_eq!(world.get::<Position>(b), None);
+        world.insert(a, Position::default());
+        assert_eq!(world.get::<Position>(b), None);
+    }
+
+    #[test]
+    fn queries_skip_entities_without_the_component() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..4).map(|_| world.spawn()).collect();
+        world.insert(entities[1], Glyph(Cell::from('a')));
+        world.insert(entities[3], Glyph(Cell::from('b')));
+        let found: Vec<Entity> = world.read::<Glyph>().iter().map(|(e, _)| e).collect();
+        assert_eq!(found, [entities[1], entities[3]]);
+        assert_eq!(world.entities().count(), 4);
+    }
+
+    #[test]
+    fn drawing_blends_from_the_previous_position() {
+        let mut world = World::new();
+        let moved = world.spawn();
+        world.insert(moved, Position { x: 2.0, y: 0.0 });
+        world.insert(moved, Glyph(Cell::from('m')));
+        remember_positions(&world);
+        world.insert(moved, Position { x: 6.0, y: 0.0 });
+        // spawned after the tick started, so drawn where it is
+        let new = world.spawn();
+        world.insert(new, Position { x: 1.0, y: 1.0 });
+        world.insert(new, Glyph(Cell::from('n')));
+
+        let mut canvas = Canvas::new(8, 2);
+        draw_glyphs(&world, &mut canvas, Viewport::default(), 0.25);
+        assert_eq!(canvas.get(3, 0).map(|cell| cell.ch), Some('m'));
+        assert_eq!(canvas.get(1, 1).map(|cell| cell.ch), Some('n'));
+    }
+}
@@ -0,0 +1,53 @@
+//! Sample games built on the engine, `console snake` and `console blocks`.
+
+mod blocks;
+mod snake;
+
+pub use blocks::Blocks;
+pub use snake::Snake;
+
+use super::{
+    canvas::Canvas,
+    cell::{Color, Style},
+};
+
+/// A small xorshift generator. Games seed it from the tick they start on,
+/// so replaying a recording plays out the same way.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number from 0 up to, not including, `n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
+
+/// The game over box both games show, centered on the canvas.
+fn draw_game_over(canvas: &mut Canvas, score: u32) {
+    let lines = [
+        " GAME OVER ".to_owned(),
+        format!(" score {} ", score),
+        " r restart  q quit ".to_owned(),
+    ];
+    let y = canvas.height() as i32 / 2 - 1;
+    for (i, line) in lines.iter().enumerate() {
+        let x = (canvas.width() as i32 - line.len() as i32) / 2;
+        let style = match i {
+            0 => Style::new().fg(Color::BRIGHT_RED).reverse().bold(),
+            _ => Style::new().reverse(),
+        };
+        canvas.text(x, y + i as i32, line, style);
+    }
+}
@@ -0,0 +1,362 @@
+use super::{draw_game_over, Rng};
+use crate::console_render::{
+    actions::Action,
+    canvas::Canvas,
+    cell::{Cell, Color, Style},
+    demo::Pause,
+    input::InputManager,
+    scene::{Scene, Transition},
+    Context,
+};
+
+const WIDTH: usize = 10;
+const HEIGHT: usize = 20;
+/// Ticks between falls on level 1, at the default 30 ticks per second.
+const START_FALL: u64 = 24;
+const LINES_PER_LEVEL: u32 = 10;
+/// Points for clearing 1 to 4 lines at once, times the level.
+const LINE_SCORES: [u32; 5] = [0, 100, 300, 500, 800];
+
+/// The seven pieces as cells in a 4x4 box, with their colors.
+const PIECES: [([(i32, i32); 4], Color); 7] = [
+    ([(0, 1), (1, 1), (2, 1), (3, 1)], Color::CYAN),
+    ([(1, 0), (2, 0), (1, 1), (2, 1)], Color::YELLOW),
+    ([(1, 0), (0, 1), (1, 1), (2, 1)], Color::MAGENTA),
+    ([(1, 0), (2, 0), (0, 1), (1, 1)], Color::GREEN),
+    ([(0, 0), (1, 0), (1, 1), (2, 1)], Color::RED),
+    ([(0, 0), (0, 1), (1, 1), (2, 1)], Color::BLUE),
+    ([(2, 0), (0, 1), (1, 1), (2, 1)], Color::BRIGHT_YELLOW),
+];
+
+#[derive(Clone, Copy)]
+struct Piece {
+    kind: usize,
+    cells: [(i32, i32); 4],
+    x: i32,
+    y: i32,
+}
+
+impl Piece {
+    fn new(kind: usize) -> Self {
+        Piece {
+            kind,
+            cells: PIECES[kind].0,
+            x: WIDTH as i32 / 2 - 2,
+            y: 0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        PIECES[self.kind].1
+    }
+
+    /// Board positions of the piece's cells.
+    fn positions(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells.iter().map(|&(x, y)| (self.x + x, self.y + y))
+    }
+
+    fn moved(&self, dx: i32, dy: i32) -> Self {
+        Piece {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+
+    /// Turned a quarter clockwise within its box. The square stays put.
+    fn rotated(&self) -> Self {
+        if self.kind == 1 {
+            return *self;
+        }
+        let size = if self.kind == 0 { 4 } else { 3 };
+        let mut cells = self.cells;
+        for cell in &mut cells {
+            *cell = (size - 1 - cell.1, cell.0);
+        }
+        Piece { cells, ..*self }
+    }
+}
+
+/// Stack falling pieces into full lines, which clear for points. The game
+/// speeds up every ten lines and ends when a new piece has no room.
+///
+/// Left and right move, up rotates, down drops faster and confirm drops the
+/// piece all the way.
+#[derive(Default)]
+pub struct Blocks {
+    /// Row by row, the color of each settled cell.
+    board: Vec<Option<Color>>,
+    piece: Option<Piece>,
+    next: usize,
+    score: u32,
+    best: u32,
+    lines: u32,
+    /// Tick of the last fall.
+    last_fall: u64,
+    game_over: bool,
+    rng: Option<Rng>,
+}
+
+impl Blocks {
+    fn start(&mut self, context: &Context) {
+        self.board = vec![None; WIDTH * HEIGHT];
+        self.score = 0;
+        self.lines = 0;
+        self.last_fall = context.time.tick;
+        self.game_over = false;
+        let mut rng = Rng::new(context.time.tick);
+        self.next = rng.below(PIECES.len());
+        self.rng = Some(rng);
+        self.spawn();
+    }
+
+    fn level(&self) -> u32 {
+        self.lines / LINES_PER_LEVEL + 1
+    }
+
+    fn fall_interval(&self) -> u64 {
+        START_FALL
+            .saturating_sub(2 * (self.level() as u64 - 1))
+            .max(2)
+    }
+
+    /// Bring in the next piece, or end the game when it does not fit.
+    fn spawn(&mut self) {
+        let piece = Piece::new(self.next);
+        if let Some(rng) = &mut self.rng {
+            self.next = rng.below(PIECES.len());
+        }
+        if self.fits(&piece) {
+            self.piece = Some(piece);
+        } else {
+            self.piece = None;
+            self.game_over = true;
+            self.best = self.best.max(self.score);
+        }
+    }
+
+    fn fits(&self, piece: &Piece) -> bool {
+        piece.positions().all(|(x, y)| {
+            let inside = x >= 0 && x < WIDTH as i32 && y >= 0 && y < HEIGHT as i32;
+            // `board` is empty before the first start
+            inside && self.board.get(y as usize * WIDTH + x as usize) == Some(&None)
+        })
+    }
+
+    /// Move the piece if it fits there, returns whether it moved.
+    fn try_move(&mut self, dx: i32, dy: i32) -> bool {
+        let Some(piece) = self.piece else {
+            return false;
+        };
+        let moved = piece.moved(dx, dy);
+        if !self.fits(&moved) {
+            return false;
+        }
+        self.piece = Some(moved);
+        true
+    }
+
+    /// Rotate, nudging the piece sideways when it turns into a wall.
+    fn rotate(&mut self) {
+        let Some(piece) = self.piece else {
+            return;
+        };
+        let rotated = piece.rotated();
+        for kick in [0, -1, 1, -2, 2] {
+            let kicked = rotated.moved(kick, 0);
+            if self.fits(&kicked) {
+                self.piece = Some(kicked);
+                return;
+            }
+        }
+    }
+
+    /// Move down a row or, when it cannot, settle the piece.
+    fn fall(&mut self) {
+        if !self.try_move(0, 1) {
+            self.lock();
+        }
+    }
+
+    fn hard_drop(&mut self) {
+        while self.try_move(0, 1) {
+            self.score += 2;
+        }
+        self.lock();
+    }
+
+    /// Settle the piece into the board, clear full lines and spawn the next.
+    fn lock(&mut self) {
+        let Some(piece) = self.piece.take() else {
+            return;
+        };
+        for (x, y) in piece.positions() {
+            self.board[y as usize * WIDTH + x as usize] = Some(piece.color());
+        }
+
+        let rows: Vec<&[Option<Color>]> = self.board.chunks(WIDTH).collect();
+        let kept: Vec<Option<Color>> = rows
+            .iter()
+            .filter(|row| row.iter().any(Option::is_none))
+            .flat_map(|row| row.iter().copied())
+            .collect();
+        let cleared = HEIGHT - kept.len() / WIDTH;
+        self.board = vec![None; cleared * WIDTH];
+        self.board.extend(kept);
+
+        self.score += LINE_SCORES[cleared] * self.level();
+        self.lines += cleared as u32;
+        self.spawn();
+    }
+}
+
+impl Scene for Blocks {
+    fn on_enter(&mut self, context: &mut Context) {
+        self.start(context);
+    }
+
+    fn update(&mut self, input: &InputManager, context: &mut Context) -> Transition {
+        let actions = input.actions();
+        if actions.just_pressed(Action::Quit) {
+            return Transition::Quit;
+        }
+        if self.game_over {
+            if actions.just_pressed(Action::Restart) || actions.just_pressed(Action::Confirm) {
+                self.start(context);
+            }
+            return Transition::None;
+        }
+        if actions.just_pressed(Action::Restart) {
+            self.start(context);
+            return Transition::None;
+        }
+        if actions.just_pressed(Action::Pause) {
+            return Transition::Push(Box::new(Pause));
+        }
+
+        if actions.is_triggered(Action::MoveLeft) {
+            self.try_move(-1, 0);
+        }
+        if actions.is_triggered(Action::MoveRight) {
+            self.try_move(1, 0);
+        }
+        if actions.is_triggered(Action::MoveUp) {
+            self.rotate();
+        }
+        if actions.just_pressed(Action::Confirm) {
+            self.hard_drop();
+            self.last_fall = context.time.tick;
+        } else if actions.is_triggered(Action::MoveDown) {
+            if self.try_move(0, 1) {
+                self.score += 1;
+            }
+            self.last_fall = context.time.tick;
+        } else if context.time.tick - self.last_fall >= self.fall_interval() {
+            self.last_fall = context.time.tick;
+            self.fall();
+        }
+        Transition::None
+    }
+
+    fn draw(&mut self, canvas: &mut Canvas, context: &Context) {
+        // two columns per cell keep blocks roughly square
+        let board_width = WIDTH as i32 * 2 + 2;
+        let left = (context.width as i32 - board_width - 12) / 2;
+        let top = (context.height as i32 - HEIGHT as i32 - 1) / 2;
+        let wall = Cell::new('|', Style::new().fg(Color::BRIGHT_BLACK));
+        canvas.line(left, top, left, top + HEIGHT as i32, wall);
+        canvas.line(
+            left + board_width - 1,
+            top,
+            left + board_width - 1,
+            top + HEIGHT as i32,
+            wall,
+        );
+        canvas.line(
+            left,
+            top + HEIGHT as i32,
+            left + board_width - 1,
+            top + HEIGHT as i32,
+            Cell::new('=', Style::new().fg(Color::BRIGHT_BLACK)),
+        );
+
+        let block = |canvas: &mut Canvas, x: i32, y: i32, color: Color| {
+            let style = Style::new().fg(color).bold();
+            canvas.text(left + 1 + x * 2, top + y, "[]", style);
+        };
+        for (i, cell) in self.board.iter().enumerate() {
+            if let Some(color) = cell {
+                block(canvas, (i % WIDTH) as i32, (i / WIDTH) as i32, *color);
+            }
+        }
+        if let Some(piece) = &self.piece {
+            for (x, y) in piece.positions() {
+                block(canvas, x, y, piece.color());
+            }
+        }
+
+        let info = left + board_width + 2;
+        let text = Style::new();
+        canvas.text(info, top, &format!("score {}", self.score), text);
+        canvas.text(info, top + 1, &format!("best  {}", self.best), text);
+        canvas.text(info, top + 2, &format!("lines {}", self.lines), text);
+        canvas.text(info, top + 3, &format!("level {}", self.level()), text);
+        canvas.text(info, top + 5, "next", text);
+        let next = Piece::new(self.next);
+        for &(x, y) in &next.cells {
+            let style = Style::new().fg(next.color()).bold();
+            canvas.text(info + x * 2, top + 6 + y, "[]", style);
+        }
+
+        if self.game_over {
+            draw_game_over(canvas, self.score);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks() -> Blocks {
+        let mut blocks = Blocks::default();
+        blocks.start(&Context::default());
+        blocks
+    }
+
+    #[test]
+    fn full_lines_clear_and_score() {
+        let mut blocks = blocks();
+        // fill the bottom row except where the I piece lands flat
+        for x in 0..WIDTH {
+            if !(3..7).contains(&x) {
+                blocks.board[(HEIGHT - 1) * WIDTH + x] = Some(Color::RED);
+            }
+        }
+        blocks.board[(HEIGHT - 2) * WIDTH] = Some(Color::BLUE);
+        blocks.piece = Some(Piece::new(0));
+        blocks.hard_drop();
+
+        assert_eq!(blocks.lines, 1);
+        assert_eq!(blocks.score, 2 * 18 + 100);
+        // the row above moved down
+        assert_eq!(blocks.board[(HEIGHT - 1) * WIDTH], Some(Color::BLUE));
+        assert!(blocks.board[..(HEIGHT - 1) * WIDTH]
+            .iter()
+            .all(Option::is_none));
+    }
+
+    #[test]
+    fn rotation_kicks_off_walls_and_a_full_board_ends_the_game() {
+        let mut blocks = blocks();
+        let mut piece = Piece::new(0).rotated();
+        piece.x = -2;
+        blocks.piece = Some(piece);
+        blocks.rotate();
+        assert!(blocks.fits(&blocks.piece.unwrap()));
+
+        blocks.board = vec![Some(Color::RED); WIDTH * HEIGHT];
+        blocks.spawn();
+        assert!(blocks.game_over);
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+
+use super::{draw_game_over, Rng};
+use crate::console_render::{
+    actions::Action,
+    canvas::Canvas,
+    cell::{Cell, Color, Style},
+    demo::Pause,
+    input::InputManager,
+    scene::{Scene, Transition},
+    Context,
+};
+
+/// Ticks between steps at the start, at the default 30 ticks per second.
+const START_INTERVAL: u64 = 6;
+const FASTEST_INTERVAL: u64 = 2;
+/// Food eaten before the snake speeds up by a tick.
+const SPEED_UP_EVERY: u32 = 5;
+
+/// Steer a growing snake to the food without running into the walls or
+/// itself. The canvas is the field, minus the bottom row for the score.
+#[derive(Default)]
+pub struct Snake {
+    width: i32,
+    height: i32,
+    /// Head first.
+    body: VecDeque<(i32, i32)>,
+    direction: (i32, i32),
+    /// Turns pressed since the last step, so two quick presses both count.
+    turns: VecDeque<(i32, i32)>,
+    food: (i32, i32),
+    score: u32,
+    best: u32,
+    /// Tick of the last step.
+    last_step: u64,
+    game_over: bool,
+    rng: Option<Rng>,
+}
+
+impl Snake {
+    fn start(&mut self, context: &Context) {
+        self.width = context.width as i32;
+        self.height = context.height as i32 - 1;
+        let (x, y) = (self.width / 2, self.height / 2);
+        self.body = (0..3).map(|i| (x - i, y)).collect();
+        self.direction = (1, 0);
+        self.turns.clear();
+        self.score = 0;
+        self.last_step = context.time.tick;
+        self.rng = Some(Rng::new(context.time.tick));
+        // a canvas too small to leave room for food
+        self.game_over = !self.place_food();
+    }
+
+    fn interval(&self) -> u64 {
+        START_INTERVAL
+            .saturating_sub((self.score / SPEED_UP_EVERY) as u64)
+            .max(FASTEST_INTERVAL)
+    }
+
+    /// Put the food on a free cell. False once the snake fills the field.
+    fn place_food(&mut self) -> bool {
+        let Some(rng) = &mut self.rng else {
+            return true;
+        };
+        let free: Vec<(i32, i32)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|cell| !self.body.contains(cell))
+            .collect();
+        if free.is_empty() {
+            return false;
+        }
+        self.food = free[rng.below(free.len())];
+        true
+    }
+
+    fn end(&mut self) {
+        self.game_over = true;
+        self.best = self.best.max(self.score);
+    }
+
+    fn steer(&mut self, input: &InputManager) {
+        let actions = input.actions();
+        let turns = [
+            (Action::MoveUp, (0, -1)),
+            (Action::MoveDown, (0, 1)),
+            (Action::MoveLeft, (-1, 0)),
+            (Action::MoveRight, (1, 0)),
+        ];
+        for (action, turn) in turns {
+            if actions.just_pressed(action) && self.turns.len() < 3 {
+                self.turns.push_back(turn);
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        while let Some((dx, dy)) = self.turns.pop_front() {
+            // turning back into the body is not a turn
+            if (dx, dy) != (-self.direction.0, -self.direction.1) && (dx, dy) != self.direction {
+                self.direction = (dx, dy);
+                break;
+            }
+        }
+
+        let (x, y) = self.body[0];
+        let head = (x + self.direction.0, y + self.direction.1);
+        let eats = head == self.food;
+        // unless it grows, the tail moves out of the way before the head moves in
+        let solid = self.body.len() - usize::from(!eats);
+        let outside = head.0 < 0 || head.1 < 0 || head.0 >= self.width || head.1 >= self.height;
+        if outside || self.body.range(..solid).any(|&cell| cell == head) {
+            self.end();
+            return;
+        }
+        if !eats {
+            self.body.pop_back();
+        }
+        self.body.push_front(head);
+        if eats {
+            self.score += 1;
+            // nothing left to eat, the snake won
+            if !self.place_food() {
+                self.end();
+            }
+        }
+    }
+}
+
+impl Scene for Snake {
+    fn on_enter(&mut self, context: &mut Context) {
+        self.start(context);
+    }
+
+    fn update(&mut self, input: &InputManager, context: &mut Context) -> Transition {
+        let actions = input.actions();
+        if actions.just_pressed(Action::Quit) {
+            return Transition::Quit;
+        }
+        if self.game_over {
+            if actions.just_pressed(Action::Restart) || actions.just_pressed(Action::Confirm) {
+                self.start(context);
+            }
+            return Transition::None;
+        }
+        if actions.just_pressed(Action::Restart) {
+            self.start(context);
+            return Transition::None;
+        }
+        if actions.just_pressed(Action::Pause) {
+            return Transition::Push(Box::new(Pause));
+        }
+
+        self.steer(input);
+        if context.time.tick - self.last_step >= self.interval() {
+            self.last_step = context.time.tick;
+            self.step();
+        }
+        Transition::None
+    }
+
+    fn draw(&mut self, canvas: &mut Canvas, context: &Context) {
+        canvas.draw_cell(
+            self.food.0,
+            self.food.1,
+            Cell::new('*', Style::new().fg(Color::BRIGHT_RED).bold()),
+        );
+        let body = Style::new().fg(Color::GREEN);
+        for &(x, y) in self.body.iter().skip(1) {
+            canvas.draw_cell(x, y, Cell::new('o', body));
+        }
+        if let Some(&(x, y)) = self.body.front() {
+            canvas.draw_cell(x, y, Cell::new('@', body.fg(Color::BRIGHT_GREEN).bold()));
+        }
+
+        let status = format!("score {}  best {}", self.score, self.best);
+        let y = context.height as i32 - 1;
+        canvas.fill_rect(
+            0,
+            y,
+            context.width as i32,
+            1,
+            Cell::new(' ', Style::new().reverse()),
+        );
+        canvas.text(0, y, &status, Style::new().reverse());
+
+        if self.game_over {
+            draw_game_over(canvas, self.score);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake() -> Snake {
+        let context = Context {
+            width: 10,
+            height: 6,
+            ..Context::default()
+        };
+        let mut snake = Snake::default();
+        snake.start(&context);
+        snake
+    }
+
+    #[test]
+    fn eating_grows_and_scores() {
+        let mut snake = snake();
+        snake.food = (6, 2);
+        snake.step();
+        assert_eq!(snake.score, 1);
+        assert_eq!(snake.body.len(), 4);
+        assert_ne!(snake.food, (6, 2));
+        assert!(!snake.body.contains(&snake.food));
+    }
+
+    #[test]
+    fn reversing_is_ignored_and_walls_end_the_game() {
+        let mut snake = snake();
+        snake.food = (0, 0);
+        snake.turns.push_back((-1, 0));
+        snake.step();
+        assert_eq!(snake.body[0], (6, 2));
+        for _ in 0..4 {
+            snake.step();
+        }
+        assert!(snake.game_over);
+        assert_eq!(snake.body[0], (9, 2));
+    }
+
+    #[test]
+    fn filling_the_field_ends_the_game() {
+        let mut snake = snake();
+        snake.width = 4;
+        snake.height = 1;
+        snake.body = [(2, 0), (1, 0), (0, 0)].into();
+        snake.food = (3, 0);
+        snake.step();
+        assert_eq!(snake.body.len(), 4);
+        assert!(snake.game_over);
+        assert_eq!(snake.best, 1);
+    }
+}
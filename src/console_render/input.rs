@@ -0,0 +1,606 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use super::{
+    actions::{Actions, Bindings},
+    backend::{InputBackend, TerminalInput},
+    recording::{InputEvent, Recording},
+};
+
+/// A key as decoded from the terminal's input stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    /// Ctrl plus a letter, e.g. `Ctrl('c')`.
+    Ctrl(char),
+    /// Alt (meta) plus a character, sent by terminals as ESC + char.
+    Alt(char),
+    Enter,
+    Tab,
+    Backspace,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// Function keys F1 to F12.
+    F(u8),
+}
+
+impl From<char> for Key {
+    fn from(c: char) -> Self {
+        Key::Char(c)
+    }
+}
+
+/// Key names as used in bindings and recordings: single characters,
+/// `Space`, `Enter`, `Up`, `F1`, `Ctrl-c`, `Alt-x` and so on.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Ctrl(c) => write!(f, "Ctrl-{}", c),
+            Key::Alt(c) => write!(f, "Alt-{}", c),
+            Key::F(n) => write!(f, "F{}", n),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let unknown = || format!("unknown key `{}`", text);
+        let single = |text: &str| {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        };
+
+        if let Some(c) = single(text) {
+            return Ok(Key::Char(c));
+        }
+        let key = match text {
+            "Space" => Key::Char(' '),
+            "Enter" => Key::Enter,
+            "Tab" => Key::Tab,
+            "Backspace" => Key::Backspace,
+            "Esc" => Key::Esc,
+            "Up" => Key::Up,
+            "Down" => Key::Down,
+            "Left" => Key::Left,
+            "Right" => Key::Right,
+            "Home" => Key::Home,
+            "End" => Key::End,
+            "PageUp" => Key::PageUp,
+            "PageDown" => Key::PageDown,
+            "Insert" => Key::Insert,
+            "Delete" => Key::Delete,
+            _ => {
+                if let Some(n) = text.strip_prefix('F') {
+                    return n
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=12).contains(n))
+                        .map(Key::F)
+                        .ok_or_else(unknown);
+                }
+                let (modifier, c) = text.split_once('-').ok_or_else(unknown)?;
+                match (modifier, single(c)) {
+                    ("Ctrl", Some(c)) if c.is_ascii_alphabetic() => {
+                        Key::Ctrl(c.to_ascii_lowercase())
+                    }
+                    ("Alt", Some(c)) => Key::Alt(c),
+                    _ => return Err(unknown()),
+                }
+            }
+        };
+        Ok(key)
+    }
+}
+
+/// Where a key is in its press/release cycle as of the last [`InputManager::update`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    Up,
+    /// Went down this frame.
+    Pressed,
+    /// Has been down since an earlier frame.
+    Held,
+    /// Went up this frame.
+    Released,
+}
+
+/// Terminals only report key presses and auto-repeats, never releases. A key
+/// counts as released once no repeat arrived for this long: the first repeat
+/// takes a while to kick in, the following ones come quickly.
+const FIRST_REPEAT_TIMEOUT: Duration = Duration::from_millis(550);
+const REPEAT_TIMEOUT: Duration = Duration::from_millis(100);
+
+struct HeldKey {
+    last_seen: Instant,
+    repeating: bool,
+}
+
+/// Where keys come from.
+enum Source {
+    /// Keys from the terminal or a test backend.
+    Live(Box<dyn InputBackend>),
+    /// The events of a recording that are still to come.
+    Replay(VecDeque<(u64, InputEvent)>),
+}
+
+pub struct InputManager {
+    held: HashMap<Key, HeldKey>,
+    states: HashMap<Key, KeyState>,
+    /// Keys that sent a press or an auto-repeat this frame.
+    triggered: HashSet<Key>,
+    source: Source,
+    /// Updates so far, what recordings count in.
+    tick: u64,
+    recording: Option<Recording>,
+    /// Canvas size as the engine last reported it, or as the replay says.
+    size: Option<(usize, usize)>,
+    bindings: Bindings,
+}
+
+impl InputManager {
+    /// Read keys from the terminal.
+    pub fn new() -> Self {
+        Self::with_backend(TerminalInput::new())
+    }
+
+    /// Read keys from another backend, like [`TestBackend`](super::TestBackend).
+    pub fn with_backend(backend: impl InputBackend + 'static) -> Self {
+        Self::with_source(Source::Live(Box::new(backend)))
+    }
+
+    /// Play back a recording instead of reading the terminal. Releases come
+    /// from the recording too, so every update sees exactly the key states
+    /// it saw when recording.
+    pub fn replay(recording: Recording) -> Self {
+        let mut input =
+            Self::with_source(Source::Replay(recording.events().iter().copied().collect()));
+        input.size = recording.size();
+        input
+    }
+
+    fn with_source(source: Source) -> Self {
+        InputManager {
+            held: HashMap::new(),
+            states: HashMap::new(),
+            triggered: HashSet::new(),
+            source,
+            tick: 0,
+            recording: None,
+            size: None,
+            bindings: Bindings::default(),
+        }
+    }
+
+    /// Whether keys come from a recording, which also decides the canvas
+    /// size, see [`InputManager::size`].
+    pub fn is_replay(&self) -> bool {
+        matches!(self.source, Source::Replay(_))
+    }
+
+    /// Whether this is a replay that has no events left.
+    pub fn replay_finished(&self) -> bool {
+        matches!(&self.source, Source::Replay(events) if events.is_empty())
+    }
+
+    /// Record every key event from the next update on, replacing a recording
+    /// in progress.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(match self.size {
+            Some((width, height)) => Recording::with_size(width, height),
+            None => Recording::default(),
+        });
+    }
+
+    /// The canvas size, which a replay keeps to what was recorded.
+    pub fn size(&self) -> Option<(usize, usize)> {
+        self.size
+    }
+
+    /// Tell the input the canvas size, so recordings keep it. The engine
+    /// does this whenever the terminal is resized.
+    pub fn set_size(&mut self, width: usize, height: usize) {
+        if self.size == Some((width, height)) {
+            return;
+        }
+        self.size = Some((width, height));
+        if let Some(recording) = &mut self.recording {
+            recording.push(self.tick, InputEvent::Resize(width, height));
+        }
+    }
+
+    /// Stop recording and return what was recorded.
+    pub fn take_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Query actions instead of keys, through the current [`Bindings`].
+    pub fn actions(&self) -> Actions<'_> {
+        Actions { input: self }
+    }
+
+    pub fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    pub fn state(&self, key: impl Into<Key>) -> KeyState {
+        self.states
+            .get(&key.into())
+            .copied()
+            .unwrap_or(KeyState::Up)
+    }
+
+    /// Whether the key is down, whether it went down this frame or earlier.
+    pub fn is_pressed(&self, key: impl Into<Key>) -> bool {
+        matches!(self.state(key), KeyState::Pressed | KeyState::Held)
+    }
+
+    pub fn just_pressed(&self, key: impl Into<Key>) -> bool {
+        self.state(key) == KeyState::Pressed
+    }
+
+    pub fn is_held(&self, key: impl Into<Key>) -> bool {
+        self.state(key) == KeyState::Held
+    }
+
+    pub fn just_released(&self, key: impl Into<Key>) -> bool {
+        self.state(key) == KeyState::Released
+    }
+
+    /// Whether the key was pressed or auto-repeated this frame, i.e. what a
+    /// text field would treat as one keystroke.
+    pub fn is_triggered(&self, key: impl Into<Key>) -> bool {
+        self.triggered.contains(&key.into())
+    }
+
+    /// Advance key states by one frame and take in whatever arrived since.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        self.triggered.clear();
+
+        // last frame's edges settle
+        self.states.retain(|_, state| match state {
+            KeyState::Pressed => {
+                *state = KeyState::Held;
+                true
+            }
+            KeyState::Released => false,
+            _ => true,
+        });
+
+        let mut events = Vec::new();
+        match &mut self.source {
+            Source::Live(backend) => {
+                events.extend(backend.poll().into_iter().map(InputEvent::Down));
+                for (key, held) in &self.held {
+                    let timeout = if held.repeating {
+                        REPEAT_TIMEOUT
+                    } else {
+                        FIRST_REPEAT_TIMEOUT
+                    };
+                    // a key that just repeated is still down
+                    let repeated = events.contains(&InputEvent::Down(*key));
+                    if !repeated && now.duration_since(held.last_seen) >= timeout {
+                        events.push(InputEvent::Up(*key));
+                    }
+                }
+            }
+            Source::Replay(queue) => {
+                while let Some(&(_, event)) = queue.front().filter(|(tick, _)| *tick <= self.tick) {
+                    events.push(event);
+                    queue.pop_front();
+                }
+            }
+        }
+
+        for event in events {
+            match event {
+                InputEvent::Down(key) => self.key_down(key, now),
+                InputEvent::Up(key) => self.key_up(key),
+                InputEvent::Resize(width, height) => self.size = Some((width, height)),
+            }
+            if let Some(recording) = &mut self.recording {
+                recording.push(self.tick, event);
+            }
+        }
+        self.tick += 1;
+    }
+
+    /// A press or an auto-repeat.
+    fn key_down(&mut self, key: Key, now: Instant) {
+        self.triggered.insert(key);
+        match self.held.get_mut(&key) {
+            Some(held) => {
+                held.last_seen = now;
+                held.repeating = true;
+            }
+            None => {
+                self.held.insert(
+                    key,
+                    HeldKey {
+                        last_seen: now,
+                        repeating: false,
+                    },
+                );
+                self.states.insert(key, KeyState::Pressed);
+            }
+        }
+    }
+
+    fn key_up(&mut self, key: Key) {
+        if self.held.remove(&key).is_some() {
+            self.states.insert(key, KeyState::Released);
+        }
+    }
+}
+
+impl Default for InputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode a chunk of raw terminal input into keys. Terminals write escape
+/// sequences in one go, so a lone ESC at the end of a chunk is the Esc key.
+pub fn decode(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (key, len) = match bytes[i] {
+            0x1B => decode_escape(&bytes[i..]),
+            b'\r' | b'\n' => (Some(Key::Enter), 1),
+            b'\t' => (Some(Key::Tab), 1),
+            0x7F | 0x08 => (Some(Key::Backspace), 1),
+            b @ 0x01..=0x1A => (Some(Key::Ctrl((b'a' + b - 1) as char)), 1),
+            b if b < 0x20 => (None, 1),
+            _ => decode_utf8(&bytes[i..]),
+        };
+        if let Some(key) = key {
+            keys.push(key);
+        }
+        i += len;
+    }
+
+    keys
+}
+
+fn decode_utf8(bytes: &[u8]) -> (Option<Key>, usize) {
+    let len = match bytes[0] {
+        b if b < 0x80 => 1,
+        b if b >> 5 == 0b110 => 2,
+        b if b >> 4 == 0b1110 => 3,
+        b if b >> 3 == 0b11110 => 4,
+        _ => return (None, 1),
+    };
+    match std::str::from_utf8(&bytes[..len.min(bytes.len())]) {
+        Ok(s) => (s.chars().next().map(Key::Char), len),
+        // skip only the bad lead byte, what follows may be a key of its own
+        Err(_) => (None, 1),
+    }
+}
+
+/// Decode a sequence starting with ESC, returns the key and the bytes used.
+fn decode_escape(bytes: &[u8]) -> (Option<Key>, usize) {
+    match bytes.get(1) {
+        None => (Some(Key::Esc), 1),
+        Some(b'[') => decode_csi(bytes),
+        Some(b'O') => {
+            // SS3, sent for arrows in application mode and for F1-F4
+            let key = match bytes.get(2) {
+                Some(b'A') => Key::Up,
+                Some(b'B') => Key::Down,
+                Some(b'C') => Key::Right,
+                Some(b'D') => Key::Left,
+                Some(b'H') => Key::Home,
+                Some(b'F') => Key::End,
+                Some(b'P') => Key::F(1),
+                Some(b'Q') => Key::F(2),
+                Some(b'R') => Key::F(3),
+                Some(b'S') => Key::F(4),
+                Some(_) => return (None, 3),
+                None => return (Some(Key::Alt('O')), 2),
+            };
+            (Some(key), 3)
+        }
+        Some(0x1B) => (Some(Key::Esc), 1),
+        Some(_) => {
+            let (key, len) = decode_utf8(&bytes[1..]);
+            (
+                key.map(|key| match key {
+                    Key::Char(c) => Key::Alt(c),
+                    key => key,
+                }),
+                len + 1,
+            )
+        }
+    }
+}
+
+/// Decode a CSI sequence (`ESC [ params final`).
+fn decode_csi(bytes: &[u8]) -> (Option<Key>, usize) {
+    // the Linux console sends F1-F5 as `ESC [ [ A` to `ESC [ [ E`
+    if bytes.get(2) == Some(&b'[') {
+        return match bytes.get(3) {
+            Some(&b @ b'A'..=b'E') => (Some(Key::F(b - b'A' + 1)), 4),
+            _ => (None, bytes.len().min(4)),
+        };
+    }
+
+    let Some(end) = bytes[2..]
+        .iter()
+        .position(|b| (0x40..=0x7E).contains(b))
+        .map(|i| i + 2)
+    else {
+        // unterminated, drop the rest of the chunk
+        return (None, bytes.len());
+    };
+
+    let params = std::str::from_utf8(&bytes[2..end]).unwrap_or("");
+    // modifiers (`1;5A` for Ctrl+Up) are ignored
+    let first: Option<u8> = params.split(';').next().and_then(|p| p.parse().ok());
+
+    let key = match (bytes[end], first) {
+        (b'A', _) => Some(Key::Up),
+        (b'B', _) => Some(Key::Down),
+        (b'C', _) => Some(Key::Right),
+        (b'D', _) => Some(Key::Left),
+        (b'H', _) => Some(Key::Home),
+        (b'F', _) => Some(Key::End),
+        (b'P', _) => Some(Key::F(1)),
+        (b'Q', _) => Some(Key::F(2)),
+        (b'R', _) => Some(Key::F(3)),
+        (b'S', _) => Some(Key::F(4)),
+        (b'~', Some(n)) => match n {
+            1 | 7 => Some(Key::Home),
+            2 => Some(Key::Insert),
+            3 => Some(Key::Delete),
+            4 | 8 => Some(Key::End),
+            5 => Some(Key::PageUp),
+            6 => Some(Key::PageDown),
+            11..=15 => Some(Key::F(n - 10)),
+            17..=21 => Some(Key::F(n - 11)),
+            23 | 24 => Some(Key::F(n - 12)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    (key, end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(cases: &[(&[u8], &[Key])]) {
+        for &(bytes, keys) in cases {
+            assert_eq!(decode(bytes), keys, "decoding {:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn decodes_arrows_and_navigation_keys() {
+        check(&[
+            (b"\x1b[A", &[Key::Up]),
+            (b"\x1b[B", &[Key::Down]),
+            (b"\x1b[C", &[Key::Right]),
+            (b"\x1b[D", &[Key::Left]),
+            (b"\x1bOA", &[Key::Up]),
+            (b"\x1bOB", &[Key::Down]),
+            (b"\x1bOC", &[Key::Right]),
+            (b"\x1bOD", &[Key::Left]),
+            // Ctrl+Up, the modifier is dropped
+            (b"\x1b[1;5A", &[Key::Up]),
+            (b"\x1b[H", &[Key::Home]),
+            (b"\x1bOH", &[Key::Home]),
+            (b"\x1b[1~", &[Key::Home]),
+            (b"\x1b[7~", &[Key::Home]),
+            (b"\x1b[F", &[Key::End]),
+            (b"\x1bOF", &[Key::End]),
+            (b"\x1b[4~", &[Key::End]),
+            (b"\x1b[8~", &[Key::End]),
+            (b"\x1b[2~", &[Key::Insert]),
+            (b"\x1b[3~", &[Key::Delete]),
+            (b"\x1b[5~", &[Key::PageUp]),
+            (b"\x1b[6~", &[Key::PageDown]),
+            (b"\x1b[A\x1b[B", &[Key::Up, Key::Down]),
+        ]);
+    }
+
+    #[test]
+    fn decodes_function_keys() {
+        check(&[
+            (b"\x1bOP", &[Key::F(1)]),
+            (b"\x1bOQ", &[Key::F(2)]),
+            (b"\x1bOR", &[Key::F(3)]),
+            (b"\x1bOS", &[Key::F(4)]),
+            (b"\x1b[P", &[Key::F(1)]),
+            (b"\x1b[S", &[Key::F(4)]),
+            (b"\x1b[[A", &[Key::F(1)]),
+            (b"\x1b[[E", &[Key::F(5)]),
+            (b"\x1b[11~", &[Key::F(1)]),
+            (b"\x1b[15~", &[Key::F(5)]),
+            (b"\x1b[17~", &[Key::F(6)]),
+            (b"\x1b[18~", &[Key::F(7)]),
+            (b"\x1b[19~", &[Key::F(8)]),
+            (b"\x1b[20~", &[Key::F(9)]),
+            (b"\x1b[21~", &[Key::F(10)]),
+            (b"\x1b[23~", &[Key::F(11)]),
+            (b"\x1b[24~", &[Key::F(12)]),
+            (b"\x1b[15;2~", &[Key::F(5)]),
+        ]);
+    }
+
+    #[test]
+    fn decodes_esc_alt_ctrl_and_text() {
+        check(&[
+            (b"\x1b", &[Key::Esc]),
+            (b"\x1b\x1b", &[Key::Esc, Key::Esc]),
+            (b"\x1bx", &[Key::Alt('x')]),
+            (b"\x1bO", &[Key::Alt('O')]),
+            ("\x1bé".as_bytes(), &[Key::Alt('é')]),
+            (b"\x01", &[Key::Ctrl('a')]),
+            (b"\x03", &[Key::Ctrl('c')]),
+            (b"\x1a", &[Key::Ctrl('z')]),
+            (b"\r\n\t", &[Key::Enter, Key::Enter, Key::Tab]),
+            (b"\x7f\x08", &[Key::Backspace, Key::Backspace]),
+            (b"\x00\x1c", &[]),
+            (
+                "aé→😀".as_bytes(),
+                &[
+                    Key::Char('a'),
+                    Key::Char('é'),
+                    Key::Char('→'),
+                    Key::Char('😀'),
+                ],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn skips_truncated_and_unknown_sequences() {
+        check(&[
+            (b"", &[]),
+            // unterminated CSI, the rest of the chunk is dropped
+            (b"\x1b[", &[]),
+            (b"\x1b[12", &[]),
+            (b"\x1b[[", &[]),
+            (b"\x1b[[Zq", &[Key::Char('q')]),
+            (b"\x1b[99~q", &[Key::Char('q')]),
+            (b"\x1b[Zq", &[Key::Char('q')]),
+            (b"\x1bOzq", &[Key::Char('q')]),
+            (b"\x1b[~q", &[Key::Char('q')]),
+            // stray and cut off UTF-8 bytes don't swallow the next key
+            (b"\xffq", &[Key::Char('q')]),
+            (b"\x80q", &[Key::Char('q')]),
+            (b"\xe2\x86q", &[Key::Char('q')]),
+            (b"\xe2\x86", &[]),
+            (b"\x1b\xffq", &[Key::Char('q')]),
+        ]);
+    }
+}
@@ -0,0 +1,71 @@
+//! Loading the line-oriented text files the engine reads: key bindings,
+//! tilemaps, recordings and sprites all fail the same few ways.
+
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// Everything that can go wrong loading one of the engine's text files.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read, `file` says what it should have held.
+    Read {
+        file: &'static str,
+        error: io::Error,
+    },
+    /// A line that does not parse.
+    Line { line: usize, reason: String },
+    /// Every line parses but something required is missing.
+    Incomplete(&'static str),
+}
+
+impl LoadError {
+    /// A [`LoadError::Line`] for the line at `index`, counting from 0.
+    pub(crate) fn line(index: usize, reason: String) -> Self {
+        LoadError::Line {
+            line: index + 1,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Read { file, .. } => write!(f, "failed to read the {}", file),
+            LoadError::Line { line, reason } => write!(f, "line {}: {}", line, reason),
+            LoadError::Incomplete(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Read { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Read the file at `path` and hand its text to `parse`.
+pub(crate) fn load<T>(
+    path: impl AsRef<Path>,
+    file: &'static str,
+    parse: impl FnOnce(&str) -> Result<T, LoadError>,
+) -> Result<T, LoadError> {
+    let text = fs::read_to_string(path).map_err(|error| LoadError::Read { file, error })?;
+    parse(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_name_the_file_or_line() {
+        let error = load("/nonexistent/level.txt", "map file", |_| Ok(())).unwrap_err();
+        assert_eq!(error.to_string(), "failed to read the map file");
+        assert!(error.source().is_some());
+        let error = LoadError::line(2, "`x` is not in the legend".to_owned());
+        assert_eq!(error.to_string(), "line 3: `x` is not in the legend");
+    }
+}
@@ -0,0 +1,377 @@
+//! Simple platformer physics: gravity, velocity integration, collision with
+//! solid tiles and overlap events between colliders.
+
+use super::{
+    ecs::{Collider, Entity, Position, Velocity},
+    tilemap::Tilemap,
+    Context,
+};
+
+/// Makes an entity with a [`Velocity`] fall and, if it has a [`Collider`],
+/// stop at solid tiles. Entities with only a collider pass through the level
+/// and just report overlaps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Body {
+    /// Downward acceleration in cells per second squared.
+    pub gravity: f32,
+    /// Whether the entity stood on a solid tile after the last tick.
+    pub grounded: bool,
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Self {
+            gravity: 30.0,
+            grounded: false,
+        }
+    }
+}
+
+/// An axis-aligned box in level coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Aabb {
+    pub fn new(position: Position, collider: Collider) -> Self {
+        Self {
+            x: position.x,
+            y: position.y,
+            width: collider.width,
+            height: collider.height,
+        }
+    }
+
+    /// Whether the boxes share some area, touching edges do not count.
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    /// The tiles the box covers, as inclusive `(left, top, right, bottom)`.
+    fn tiles(&self) -> (i32, i32, i32, i32) {
+        (
+            self.x.floor() as i32,
+            self.y.floor() as i32,
+            (self.x + self.width).ceil() as i32 - 1,
+            (self.y + self.height).ceil() as i32 - 1,
+        )
+    }
+}
+
+/// Something game logic may want to react to, collected in
+/// [`Context::collisions`] during each tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collision {
+    /// An entity ran into the solid tile at `x`, `y` and stopped. The normal
+    /// points away from the tile, `(0, -1)` when landing on it.
+    Tile {
+        entity: Entity,
+        x: i32,
+        y: i32,
+        normal: (i32, i32),
+    },
+    /// The colliders of two entities overlap.
+    Overlap(Entity, Entity),
+}
+
+/// Apply gravity, move everything with a velocity by one tick, stop
+/// colliders at solid tiles and report overlapping colliders.
+///
+/// Collisions from the previous tick are cleared first, scenes see them in
+/// the update of the following tick.
+pub fn physics(context: &mut Context) {
+    context.collisions.clear();
+    let dt = context.time.dt;
+    let world = &context.world;
+    let colliders = world.read::<Collider>();
+    let mut velocities = world.write::<Velocity>();
+    let mut positions = world.write::<Position>();
+    let mut bodies = world.write::<Body>();
+
+    for (entity, velocity) in velocities.iter_mut() {
+        let Some(position) = positions.get_mut(entity) else {
+            continue;
+        };
+        let body = bodies.get_mut(entity);
+        if let Some(body) = &body {
+            velocity.y += body.gravity * dt;
+        }
+        // NaN or infinity has nowhere sensible to go
+        if !velocity.x.is_finite() || !velocity.y.is_finite() {
+            continue;
+        }
+
+        let (Some(body), Some(map), Some(&collider)) =
+            (body, &context.tilemap, colliders.get(entity))
+        else {
+            position.x += velocity.x * dt;
+            position.y += velocity.y * dt;
+            continue;
+        };
+        let mut aabb = Aabb::new(*position, collider);
+        let hit_x = sweep(map, &mut aabb, velocity.x * dt, Axis::X);
+        let hit_y = sweep(map, &mut aabb, velocity.y * dt, Axis::Y);
+        position.x = aabb.x;
+        position.y = aabb.y;
+
+        if let Some((x, y)) = hit_x {
+            let normal = (-velocity.x.signum() as i32, 0);
+            velocity.x = 0.0;
+            context.collisions.push(Collision::Tile {
+                entity,
+                x,
+                y,
+                normal,
+            });
+        }
+        let landed = hit_y.is_some() && velocity.y > 0.0;
+        if let Some((x, y)) = hit_y {
+            let normal = (0, -velocity.y.signum() as i32);
+            velocity.y = 0.0;
+            context.collisions.push(Collision::Tile {
+                entity,
+                x,
+                y,
+                normal,
+            });
+        }
+        body.grounded = landed;
+    }
+
+    let boxes: Vec<(Entity, Aabb)> = colliders
+        .iter()
+        .filter_map(|(entity, &collider)| {
+            Some((entity, Aabb::new(*positions.get(entity)?, collider)))
+        })
+        .collect();
+    for (i, (a, first)) in boxes.iter().enumerate() {
+        for (b, second) in &boxes[i + 1..] {
+            if first.overlaps(second) {
+                context.collisions.push(Collision::Overlap(*a, *b));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+}
+
+/// Move the box along one axis in steps of at most a cell, so fast entities
+/// cannot skip through thin walls. Returns the solid tile it stopped at.
+fn sweep(map: &Tilemap, aabb: &mut Aabb, distance: f32, axis: Axis) -> Option<(i32, i32)> {
+    let steps = distance.abs().ceil().max(1.0);
+    let step = distance / steps;
+    // by then the box has left the map, and outside counts as solid
+    let most = match axis {
+        Axis::X => map.width() as f32 + aabb.width.ceil() + 1.0,
+        Axis::Y => map.height() as f32 + aabb.height.ceil() + 1.0,
+    };
+    for _ in 0..steps.min(most) as u32 {
+        match axis {
+            Axis::X => aabb.x += step,
+            Axis::Y => aabb.y += step,
+        }
+        if let Some((x, y)) = solid_tile(map, aabb) {
+            // push back out to the edge of the tile
+            match axis {
+                Axis::X if step > 0.0 => aabb.x = x as f32 - aabb.width,
+                Axis::X => aabb.x = x as f32 + 1.0,
+                Axis::Y if step > 0.0 => aabb.y = y as f32 - aabb.height,
+                Axis::Y => aabb.y = y as f32 + 1.0,
+            }
+            return Some((x, y));
+        }
+    }
+    None
+}
+
+/// The first solid tile the box covers.
+fn solid_tile(map: &Tilemap, aabb: &Aabb) -> Option<(i32, i32)> {
+    let (left, top, right, bottom) = aabb.tiles();
+    (top..=bottom)
+        .flat_map(|y| (left..=right).map(move |x| (x, y)))
+        .find(|&(x, y)| map.is_solid(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "\
+legend:
+# wall solid
+
+map:
+#####
+#   #
+#   #
+#####
+";
+
+    fn context() -> Context {
+        let mut context = Context {
+            tilemap: Some(Tilemap::parse(LEVEL).unwrap()),
+            ..Context::default()
+        };
+        context.time.dt = 0.5;
+        context
+    }
+
+    #[test]
+    fn velocity_moves_entities_per_tick() {
+        let mut context = Context::default();
+        context.time.dt = 0.5;
+        let world = &mut context.world;
+        let moving = world.spawn();
+        world.insert(moving, Position::default());
+        world.insert(moving, Velocity { x: 2.0, y: -1.0 });
+        let still = world.spawn();
+        world.insert(still, Position::default());
+
+        physics(&mut context);
+        let world = &context.world;
+        assert_eq!(
+            world.get::<Position>(moving),
+            Some(Position { x: 1.0, y: -0.5 })
+        );
+        assert_eq!(world.get::<Position>(still), Some(Position::default()));
+    }
+
+    #[test]
+    fn gravity_lands_bodies_on_solid_tiles() {
+        let mut context = context();
+        let world = &mut context.world;
+        let player = world.spawn();
+        world.insert(player, Position { x: 1.0, y: 1.0 });
+        world.insert(player, Velocity::default());
+        world.insert(
+            player,
+            Collider {
+                width: 1.0,
+                height: 1.0,
+            },
+        );
+        world.insert(player, Body::default());
+
+        for _ in 0..4 {
+            physics(&mut context);
+        }
+        let world = &context.world;
+        assert_eq!(
+            world.get::<Position>(player),
+            Some(Position { x: 1.0, y: 2.0 })
+        );
+        assert_eq!(world.get::<Velocity>(player), Some(Velocity::default()));
+        assert!(world.get::<Body>(player).unwrap().grounded);
+        assert!(context.collisions.contains(&Collision::Tile {
+            entity: player,
+            x: 1,
+            y: 3,
+            normal: (0, -1),
+        }));
+    }
+
+    #[test]
+    fn fast_entities_do_not_tunnel_and_overlaps_are_reported() {
+        let mut context = context();
+        let world = &mut context.world;
+        let bullet = world.spawn();
+        world.insert(bullet, Position { x: 1.0, y: 1.0 });
+        world.insert(bullet, Velocity { x: 40.0, y: 0.0 });
+        world.insert(
+            bullet,
+            Collider {
+                width: 1.0,
+                height: 1.0,
+            },
+        );
+        world.insert(
+            bullet,
+            Body {
+                gravity: 0.0,
+                ..Body::default()
+            },
+        );
+        let target = world.spawn();
+        world.insert(target, Position { x: 3.0, y: 1.5 });
+        world.insert(
+            target,
+            Collider {
+                width: 1.0,
+                height: 1.0,
+            },
+        );
+
+        physics(&mut context);
+        assert_eq!(
+            context.world.get::<Position>(bullet),
+            Some(Position { x: 3.0, y: 1.0 })
+        );
+        assert!(context
+            .collisions
+            .contains(&Collision::Overlap(bullet, target)));
+    }
+
+    #[test]
+    fn fast_bodies_stop_at_the_first_wall() {
+        let mut context = Context {
+            tilemap: Some(Tilemap::parse("legend:\n# wall solid\nmap:\n#  #   #\n").unwrap()),
+            ..Context::default()
+        };
+        context.time.dt = 0.5;
+        let world = &mut context.world;
+        let mut spawn = |velocity| {
+            let entity = world.spawn();
+            world.insert(entity, Position { x: 1.0, y: 0.0 });
+            world.insert(entity, velocity);
+            world.insert(
+                entity,
+                Collider {
+                    width: 1.0,
+                    height: 1.0,
+                },
+            );
+            world.insert(
+                entity,
+                Body {
+                    gravity: 0.0,
+                    ..Body::default()
+                },
+            );
+            entity
+        };
+        let fast = spawn(Velocity { x: 1e9, y: 0.0 });
+        let infinite = spawn(Velocity {
+            x: f32::INFINITY,
+            y: 0.0,
+        });
+        let nan = spawn(Velocity {
+            x: 0.0,
+            y: f32::NAN,
+        });
+
+        physics(&mut context);
+        let world = &context.world;
+        assert_eq!(
+            world.get::<Position>(fast),
+            Some(Position { x: 2.0, y: 0.0 })
+        );
+        assert_eq!(world.get::<Velocity>(fast).unwrap().x, 0.0);
+        for entity in [infinite, nan] {
+            assert_eq!(
+                world.get::<Position>(entity),
+                Some(Position { x: 1.0, y: 0.0 })
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Pixels smaller than a character cell. Terminal cells are about twice as tall
+//! as they are wide, so every mode here keeps pixels close to square except
+//! [`PixelMode::Cell`].
+
+use super::{
+    canvas::Canvas,
+    cell::{Cell, Color, Style},
+};
+
+/// How [`Canvas::plot`] maps pixels onto character cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelMode {
+    /// One pixel per cell, a full block.
+    #[default]
+    Cell,
+    /// Two pixels per cell stacked vertically, `▀` with the upper pixel as the
+    /// foreground and the lower one as the background color.
+    HalfBlock,
+    /// A 2x4 grid of braille dots per cell. Dots share one color per cell,
+    /// the last one plotted wins.
+    Braille,
+}
+
+impl PixelMode {
+    /// Pixels per cell horizontally and vertically.
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            PixelMode::Cell => (1, 1),
+            PixelMode::HalfBlock => (1, 2),
+            PixelMode::Braille => (2, 4),
+        }
+    }
+}
+
+const UPPER_HALF: char = '▀';
+const LOWER_HALF: char = '▄';
+const FULL_BLOCK: char = '█';
+const BRAILLE_BLANK: u32 = 0x2800;
+
+/// Bit of each braille dot, indexed by `[y][x]` within the cell.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl Canvas {
+    /// Size of the canvas in pixels of the current [`PixelMode`].
+    pub fn pixel_size(&self) -> (usize, usize) {
+        let (w, h) = self.pixel_mode().cell_size();
+        (self.width() * w, self.height() * h)
+    }
+
+    /// Set one pixel, keeping whatever else is in its cell. Pixels outside
+    /// the canvas are ignored.
+    pub fn plot(&mut self, x: i32, y: i32, color: Color) {
+        let mode = self.pixel_mode();
+        let (w, h) = mode.cell_size();
+        let (cx, cy) = (x.div_euclid(w as i32), y.div_euclid(h as i32));
+        let (sx, sy) = (x.rem_euclid(w as i32), y.rem_euclid(h as i32));
+        let Some(&current) = self.get(cx, cy) else {
+            return;
+        };
+
+        let cell = match mode {
+            PixelMode::Cell => Cell::new(FULL_BLOCK, Style::new().fg(color)),
+            PixelMode::HalfBlock => {
+                let (mut top, mut bottom) = halves(&current);
+                if sy == 0 {
+                    top = Some(color);
+                } else {
+                    bottom = Some(color);
+                }
+                match (top, bottom) {
+                    (Some(top), Some(bottom)) => {
+                        Cell::new(UPPER_HALF, Style::new().fg(top).bg(bottom))
+                    }
+                    (Some(top), None) => Cell::new(UPPER_HALF, Style::new().fg(top)),
+                    (None, Some(bottom)) => Cell::new(LOWER_HALF, Style::new().fg(bottom)),
+                    (None, None) => unreachable!("one half was just set"),
+                }
+            }
+            PixelMode::Braille => {
+                let dots = match current.ch as u32 {
+                    c @ BRAILLE_BLANK..=0x28FF => c - BRAILLE_BLANK,
+                    _ => 0,
+                };
+                let dots = dots | BRAILLE_DOTS[sy as usize][sx as usize];
+                let ch = char::from_u32(BRAILLE_BLANK + dots).unwrap();
+                Cell::new(ch, Style::new().fg(color))
+            }
+        };
+        self.draw_cell(cx, cy, cell);
+    }
+}
+
+/// Colors of the upper and lower half of a cell drawn in half-block mode.
+fn halves(cell: &Cell) -> (Option<Color>, Option<Color>) {
+    let bg = (cell.bg != Color::Default).then_some(cell.bg);
+    match cell.ch {
+        UPPER_HALF => (Some(cell.fg), bg),
+        LOWER_HALF => (bg, Some(cell.fg)),
+        FULL_BLOCK => (Some(cell.fg), Some(cell.fg)),
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_blocks_keep_the_other_half() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel_mode(PixelMode::HalfBlock);
+        assert_eq!(canvas.pixel_size(), (2, 2));
+
+        canvas.plot(0, 1, Color::RED);
+        assert_eq!(
+            canvas.get(0, 0),
+            Some(&Cell::new(LOWER_HALF, Style::new().fg(Color::RED)))
+        );
+        canvas.plot(0, 0, Color::BLUE);
+        assert_eq!(
+            canvas.get(0, 0),
+            Some(&Cell::new(
+                UPPER_HALF,
+                Style::new().fg(Color::BLUE).bg(Color::RED)
+            ))
+        );
+    }
+
+    #[test]
+    fn braille_sets_dots() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.set_pixel_mode(PixelMode::Braille);
+        canvas.plot(0, 0, Color::GREEN);
+        canvas.plot(1, 3, Color::GREEN);
+        assert_eq!(canvas.get(0, 0).unwrap().ch, '⢁');
+        // outside, in either direction
+        canvas.plot(2, 0, Color::GREEN);
+        canvas.plot(-1, 0, Color::GREEN);
+        assert_eq!(canvas.get(0, 0).unwrap().ch, '⢁');
+    }
+}
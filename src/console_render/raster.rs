@@ -0,0 +1,178 @@
+//! CPU rasterizer for the wgpu scene, a GPU-free preview in the terminal and
+//! a reference to compare the wgpu output against.
+
+use super::{
+    canvas::Canvas,
+    cell::{Color, Style},
+};
+use crate::{app::Vertex, camera::Camera};
+
+/// Characters from dark to bright.
+pub(super) const RAMP: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// How rasterized pixels become character cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shading {
+    /// One pixel per cell, a character picked by brightness in the pixel's color.
+    #[default]
+    Ascii,
+    /// Plotted in the canvas' [`PixelMode`](super::PixelMode).
+    Pixels,
+}
+
+/// Rasterize indexed triangles into a `width` by `height` image of linear
+/// colors, `None` where nothing covers the pixel. Follows the wgpu pipeline:
+/// the camera transform from the vertex shader, counter-clockwise front faces
+/// with back faces culled, and colors interpolated across each triangle.
+pub fn rasterize(
+    vertices: &[Vertex],
+    indices: &[u16],
+    camera: &Camera,
+    aspect: f32,
+    width: usize,
+    height: usize,
+) -> Vec<Option<[f32; 3]>> {
+    let mut pixels = vec![None; width * height];
+
+    // clip space (y up) -> pixel coordinates (y down)
+    let to_pixel = |[x, y]: [f32; 2]| {
+        [
+            (x + 1.0) * 0.5 * width as f32,
+            (1.0 - y) * 0.5 * height as f32,
+        ]
+    };
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| &vertices[triangle[i] as usize]);
+        let [pa, pb, pc] = [a, b, c].map(|v| to_pixel(camera.transform(aspect, v.position)));
+
+        // flipping y turned counter-clockwise into a negative area
+        let area = edge(pa, pb, pc);
+        if area >= 0.0 {
+            continue;
+        }
+
+        let min_x = pa[0].min(pb[0]).min(pc[0]).floor().max(0.0) as usize;
+        let min_y = pa[1].min(pb[1]).min(pc[1]).floor().max(0.0) as usize;
+        let max_x = (pa[0].max(pb[0]).max(pc[0]).ceil().max(0.0) as usize).min(width);
+        let max_y = (pa[1].max(pb[1]).max(pc[1]).ceil().max(0.0) as usize).min(height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                // sample at the pixel center like the GPU does
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let wa = edge(pb, pc, p) / area;
+                let wb = edge(pc, pa, p) / area;
+                let wc = edge(pa, pb, p) / area;
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+                let color = [0, 1, 2].map(|i| wa * a.color[i] + wb * b.color[i] + wc * c.color[i]);
+                pixels[y * width + x] = Some(color);
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Rasterize the scene onto the whole canvas. Cells are about twice as tall
+/// as they are wide, which the aspect ratio given to the camera accounts for
+/// in every pixel mode.
+pub fn draw_scene(
+    canvas: &mut Canvas,
+    vertices: &[Vertex],
+    indices: &[u16],
+    camera: &Camera,
+    shading: Shading,
+) {
+    let (width, height) = (canvas.width(), canvas.height());
+    let aspect = width as f32 / (2 * height) as f32;
+
+    match shading {
+        Shading::Ascii => {
+            let pixels = rasterize(vertices, indices, camera, aspect, width, height);
+            for (i, pixel) in pixels.iter().enumerate() {
+                let Some(color) = pixel else { continue };
+                let rgb = to_srgb(*color);
+                let step = (luminance(rgb) * (RAMP.len() - 1) as f32).round() as usize;
+                // keep covered pixels visible even when they are very dark
+                let ch = RAMP[step.max(1)];
+                let (x, y) = ((i % width) as i32, (i / width) as i32);
+                canvas.draw_styled(x, y, ch, Style::new().fg(truecolor(rgb)));
+            }
+        }
+        Shading::Pixels => {
+            let (width, height) = canvas.pixel_size();
+            let pixels = rasterize(vertices, indices, camera, aspect, width, height);
+            for (i, pixel) in pixels.iter().enumerate() {
+                let Some(color) = pixel else { continue };
+                let (x, y) = ((i % width) as i32, (i / width) as i32);
+                canvas.plot(x, y, truecolor(to_srgb(*color)));
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`.
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Encode linear colors the way the sRGB surface of the wgpu app does.
+fn to_srgb(color: [f32; 3]) -> [f32; 3] {
+    color.map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.0031308 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+pub(super) fn luminance([r, g, b]: [f32; 3]) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn truecolor(rgb: [f32; 3]) -> Color {
+    let [r, g, b] = rgb.map(|c| (c * 255.0).round() as u8);
+    Color::Rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{INDICES, VERTICES};
+
+    #[test]
+    fn covers_the_center_but_not_the_corners() {
+        let pixels = rasterize(VERTICES, INDICES, &Camera::default(), 1.0, 8, 8);
+        let [r, g, b] = pixels[4 * 8 + 4].unwrap();
+        assert!((r - 0.5).abs() < 1e-5 && g.abs() < 1e-5 && (b - 0.5).abs() < 1e-5);
+        assert_eq!(pixels[0], None);
+        assert_eq!(pixels[63], None);
+    }
+
+    #[test]
+    fn back_faces_are_culled() {
+        let reversed: Vec<u16> = INDICES.iter().rev().copied().collect();
+        let pixels = rasterize(VERTICES, &reversed, &Camera::default(), 1.0, 8, 8);
+        assert!(pixels.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn colors_are_interpolated() {
+        let corner = |position, color| Vertex { position, color };
+        let vertices = [
+            corner([-1.0, -1.0, 0.0], [1.0, 0.0, 0.0]),
+            corner([1.0, -1.0, 0.0], [0.0, 1.0, 0.0]),
+            corner([-1.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+        ];
+        let pixels = rasterize(&vertices, &[0, 1, 2], &Camera::default(), 1.0, 4, 4);
+        // bottom-left pixel, mostly the red corner
+        let [r, g, b] = pixels[3 * 4].unwrap();
+        assert!(r > g && r > b);
+        assert!((r + g + b - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,255 @@
+//! Input recordings: every key event with the tick it arrived in, so a
+//! session can be played back exactly, see [`InputManager::replay`]. Games
+//! lay themselves out by the canvas size, so that is recorded too.
+//!
+//! Recordings are saved as text, the canvas size first and then one event
+//! per line:
+//!
+//! ```text
+//! size 60 20
+//! # tick event key
+//! 12 down d
+//! 16 down d
+//! 19 up d
+//! 25 resize 80 24
+//! 40 down q
+//! ```
+//!
+//! [`InputManager::replay`]: super::InputManager::replay
+
+use std::{fmt, fs, io, path::Path};
+
+use super::{
+    input::Key,
+    load::{self, LoadError},
+};
+
+/// A key going down, or auto-repeating, and coming back up. Terminals never
+/// report releases, the [`InputManager`](super::InputManager) infers them.
+/// Besides keys the canvas can change its size, in cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    Down(Key),
+    Up(Key),
+    Resize(usize, usize),
+}
+
+/// Key events in the order they happened, each with the input update it
+/// happened in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    /// Canvas size when the recording started.
+    size: Option<(usize, usize)>,
+    events: Vec<(u64, InputEvent)>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        load::load(path, "recording", Self::parse)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// An empty recording of a session starting on a `width` by `height`
+    /// canvas.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        Recording {
+            size: Some((width, height)),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LoadError> {
+        let mut recording = Recording::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |reason| LoadError::line(i, reason);
+            if let Some(size) = line.strip_prefix("size ") {
+                if recording.size.is_some() || !recording.events.is_empty() {
+                    return Err(error("`size` goes once, before the events".to_owned()));
+                }
+                recording.size = Some(parse_size(size).map_err(error)?);
+                continue;
+            }
+            let mut fields = line.splitn(3, ' ');
+            let (Some(tick), Some(kind), Some(key)) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(error("expected `<tick> down|up <key>`".to_owned()));
+            };
+            let tick: u64 = tick
+                .parse()
+                .map_err(|_| error(format!("`{}` is not a tick", tick)))?;
+            if recording
+                .events
+                .last()
+                .is_some_and(|(last, _)| *last > tick)
+            {
+                return Err(error("ticks go backwards".to_owned()));
+            }
+            let event = match kind {
+                "down" => InputEvent::Down(key.parse().map_err(error)?),
+                "up" => InputEvent::Up(key.parse().map_err(error)?),
+                "resize" => {
+                    let (width, height) = parse_size(key).map_err(error)?;
+                    InputEvent::Resize(width, height)
+                }
+                _ => return Err(error(format!("unknown event `{}`", kind))),
+            };
+            recording.events.push((tick, event));
+        }
+        Ok(recording)
+    }
+
+    /// Add an event, ticks must not go backwards.
+    pub fn push(&mut self, tick: u64, event: InputEvent) {
+        debug_assert!(self.events.last().is_none_or(|(last, _)| *last <= tick));
+        self.events.push((tick, event));
+    }
+
+    /// Canvas size when the recording started, `None` for recordings
+    /// written by hand without one.
+    pub fn size(&self) -> Option<(usize, usize)> {
+        self.size
+    }
+
+    pub fn events(&self) -> &[(u64, InputEvent)] {
+        &self.events
+    }
+}
+
+/// A canvas size written as `<width> <height>`.
+fn parse_size(text: &str) -> Result<(usize, usize), String> {
+    let error = || format!("expected `<width> <height>`, got `{}`", text);
+    let (width, height) = text.trim().split_once(' ').ok_or_else(error)?;
+    let width = width.parse().map_err(|_| error())?;
+    let height = height.trim().parse().map_err(|_| error())?;
+    Ok((width, height))
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((width, height)) = self.size {
+            writeln!(f, "size {} {}", width, height)?;
+        }
+        writeln!(f, "# tick event key")?;
+        for (tick, event) in &self.events {
+            match event {
+                InputEvent::Down(key) => writeln!(f, "{} down {}", tick, key)?,
+                InputEvent::Up(key) => writeln!(f, "{} up {}", tick, key)?,
+                InputEvent::Resize(width, height) => {
+                    writeln!(f, "{} resize {} {}", tick, width, height)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console_render::{
+        demo::Wander, ecs::Position, input::InputManager, scene::SceneStack, Context, TestBackend,
+        Tilemap,
+    };
+
+    #[test]
+    fn text_format_round_trips() {
+        let mut recording = Recording::with_size(30, 12);
+        recording.push(0, InputEvent::Down(Key::Char(' ')));
+        recording.push(3, InputEvent::Down(Key::Ctrl('c')));
+        recording.push(3, InputEvent::Up(Key::Char(' ')));
+        recording.push(9, InputEvent::Down(Key::Char('#')));
+        recording.push(9, InputEvent::Down(Key::F(11)));
+        recording.push(9, InputEvent::Resize(40, 15));
+        assert_eq!(Recording::parse(&recording.to_string()).unwrap(), recording);
+
+        let error = Recording::parse("4 down a\n2 up a\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: ticks go backwards");
+        let error = Recording::parse("1 down a\nsize 4 4\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: `size` goes once, before the events"
+        );
+    }
+
+    #[test]
+    fn replay_reproduces_key_states_per_tick() {
+        let recording = Recording::parse("1 down d\n2 down d\n4 up d\n").unwrap();
+        let mut input = InputManager::replay(recording);
+        let mut states = Vec::new();
+        while !input.replay_finished() {
+            input.update();
+            states.push((
+                input.just_pressed('d'),
+                input.is_pressed('d'),
+                input.is_triggered('d'),
+            ));
+        }
+        input.update();
+        assert!(!input.is_pressed('d'));
+        assert_eq!(
+            states,
+            [
+                (false, false, false),
+                (true, true, true),
+                (false, true, true),
+                (false, true, false),
+                (false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn replayed_session_is_deterministic() {
+        let level = "legend:\n# wall solid\n. floor\n@ floor spawn\n\nmap:\n#####\n#@..#\n#####\n";
+        let recording =
+            Recording::parse("0 down d\n5 down d\n8 up d\n9 down d\n12 up d\n").unwrap();
+
+        let run = || {
+            let mut context = Context {
+                tilemap: Some(Tilemap::parse(level).unwrap()),
+                ..Context::default()
+            };
+            let mut scenes = SceneStack::default();
+            scenes.push(Box::new(Wander::default()), &mut context);
+            let mut input = InputManager::replay(recording.clone());
+            while !input.replay_finished() {
+                input.update();
+                scenes.update(&input, &mut context);
+            }
+            context.world.get::<Position>(context.player.unwrap())
+        };
+        // three steps right, the third one into the wall
+        assert_eq!(run(), Some(Position { x: 3.0, y: 1.0 }));
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn canvas_size_is_recorded_and_replayed() {
+        let mut input = InputManager::with_backend(TestBackend::default());
+        input.set_size(3, 1);
+        input.start_recording();
+        input.update();
+        input.set_size(5, 2);
+        input.update();
+        let recording = input.take_recording().unwrap();
+        assert_eq!(
+            recording.to_string(),
+            "size 3 1\n# tick event key\n1 resize 5 2\n"
+        );
+
+        let mut input = InputManager::replay(recording);
+        assert!(input.is_replay());
+        assert_eq!(input.size(), Some((3, 1)));
+        input.update();
+        assert_eq!(input.size(), Some((3, 1)));
+        input.update();
+        assert_eq!(input.size(), Some((5, 2)));
+    }
+}
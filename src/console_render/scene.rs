@@ -0,0 +1,272 @@
+use super::{canvas::Canvas, input::InputManager, Context};
+
+/// What the engine should do with the scene stack after an update.
+pub enum Transition {
+    /// Keep the current scene.
+    None,
+    /// Put a scene on top, the current one pauses underneath.
+    Push(Box<dyn Scene>),
+    /// Leave the current scene and resume the one below.
+    Pop,
+    /// Swap the current scene for another one.
+    Replace(Box<dyn Scene>),
+    /// Leave every scene, which stops the engine.
+    Quit,
+}
+
+/// One state of a game: a menu, a level, a pause screen. The engine keeps a
+/// stack of them, only the top one updates.
+pub trait Scene {
+    /// Called when the scene is pushed on the stack.
+    fn on_enter(&mut self, _context: &mut Context) {}
+
+    /// Advance one logic tick.
+    fn update(&mut self, input: &InputManager, context: &mut Context) -> Transition;
+
+    fn draw(&mut self, canvas: &mut Canvas, context: &Context);
+
+    /// Called when the scene is popped or replaced.
+    fn on_exit(&mut self, _context: &mut Context) {}
+
+    /// Overlays, like a pause screen, are drawn on top of the scene below
+    /// them instead of hiding it.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// Whether the engine's systems run while this scene is on top. Pause
+    /// screens return false to freeze the world underneath.
+    fn runs_systems(&self) -> bool {
+        true
+    }
+}
+
+/// The scenes of an engine, the last one is on top.
+#[derive(Default)]
+pub(crate) struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn runs_systems(&self) -> bool {
+        self.scenes.last().is_some_and(|scene| scene.runs_systems())
+    }
+
+    pub fn push(&mut self, mut scene: Box<dyn Scene>, context: &mut Context) {
+        scene.on_enter(context);
+        self.scenes.push(scene);
+    }
+
+    pub fn pop(&mut self, context: &mut Context) -> Option<Box<dyn Scene>> {
+        let mut scene = self.scenes.pop()?;
+        scene.on_exit(context);
+        Some(scene)
+    }
+
+    pub fn replace(&mut self, scene: Box<dyn Scene>, context: &mut Context) {
+        self.pop(context);
+        self.push(scene, context);
+    }
+
+    pub fn clear(&mut self, context: &mut Context) {
+        while self.pop(context).is_some() {}
+    }
+
+    /// Update the top scene and apply the transition it asks for.
+    pub fn update(&mut self, input: &InputManager, context: &mut Context) {
+        let Some(top) = self.scenes.last_mut() else {
+            return;
+        };
+        match top.update(input, context) {
+            Transition::None => {}
+            Transition::Push(scene) => self.push(scene, context),
+            Transition::Pop => {
+                self.pop(context);
+            }
+            Transition::Replace(scene) => self.replace(scene, context),
+            Transition::Quit => self.clear(context),
+        }
+    }
+
+    /// Draw the top scene, and the ones below it as long as it is an overlay.
+    pub fn draw(&mut self, canvas: &mut Canvas, context: &Context) {
+        let bottom = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in &mut self.scenes[bottom..] {
+            scene.draw(canvas, context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::console_render::backend::TestBackend;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// Logs every call, and asks for `next` on its first update.
+    struct Probe {
+        name: &'static str,
+        log: Log,
+        next: Option<Transition>,
+        overlay: bool,
+        systems: bool,
+    }
+
+    impl Probe {
+        fn new(name: &'static str, log: &Log) -> Self {
+            Probe {
+                name,
+                log: log.clone(),
+                next: None,
+                overlay: false,
+                systems: true,
+            }
+        }
+
+        fn then(self, next: Transition) -> Self {
+            Probe {
+                next: Some(next),
+                ..self
+            }
+        }
+
+        fn overlay(self, systems: bool) -> Self {
+            Probe {
+                overlay: true,
+                systems,
+                ..self
+            }
+        }
+
+        fn record(&self, call: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", call, self.name));
+        }
+    }
+
+    impl Scene for Probe {
+        fn on_enter(&mut self, _context: &mut Context) {
+            self.record("enter");
+        }
+
+        fn update(&mut self, _input: &InputManager, _context: &mut Context) -> Transition {
+            self.record("update");
+            self.next.take().unwrap_or(Transition::None)
+        }
+
+        fn draw(&mut self, _canvas: &mut Canvas, _context: &Context) {
+            self.record("draw");
+        }
+
+        fn on_exit(&mut self, _context: &mut Context) {
+            self.record("exit");
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+
+        fn runs_systems(&self) -> bool {
+            self.systems
+        }
+    }
+
+    fn take(log: &Log) -> Vec<String> {
+        std::mem::take(&mut *log.borrow_mut())
+    }
+
+    #[test]
+    fn transitions_enter_and_exit_scenes_in_order() {
+        let log = Log::default();
+        let input = InputManager::with_backend(TestBackend::default());
+        let mut context = Context::default();
+        let mut stack = SceneStack::default();
+
+        let c = Probe::new("c", &log).then(Transition::Pop);
+        let b = Probe::new("b", &log).then(Transition::Replace(Box::new(c)));
+        let a = Probe::new("a", &log).then(Transition::Push(Box::new(b)));
+        stack.push(Box::new(a), &mut context);
+        assert_eq!(take(&log), ["enter a"]);
+
+        stack.update(&input, &mut context);
+        assert_eq!(take(&log), ["update a", "enter b"]);
+        stack.update(&input, &mut context);
+        assert_eq!(take(&log), ["update b", "exit b", "enter c"]);
+        stack.update(&input, &mut context);
+        assert_eq!(take(&log), ["update c", "exit c"]);
+        stack.update(&input, &mut context);
+        assert_eq!(take(&log), ["update a"]);
+
+        assert!(stack.pop(&mut context).is_some());
+        assert_eq!(take(&log), ["exit a"]);
+        assert!(stack.is_empty());
+        assert!(stack.pop(&mut context).is_none());
+        stack.update(&input, &mut context);
+        assert!(take(&log).is_empty());
+    }
+
+    #[test]
+    fn quit_and_clear_exit_every_scene_from_the_top() {
+        let log = Log::default();
+        let input = InputManager::with_backend(TestBackend::default());
+        let mut context = Context::default();
+        let mut stack = SceneStack::default();
+
+        stack.push(Box::new(Probe::new("a", &log)), &mut context);
+        stack.push(Box::new(Probe::new("b", &log)), &mut context);
+        let quit = Probe::new("c", &log).then(Transition::Quit);
+        stack.push(Box::new(quit), &mut context);
+        take(&log);
+        stack.update(&input, &mut context);
+        assert_eq!(take(&log), ["update c", "exit c", "exit b", "exit a"]);
+        assert!(stack.is_empty());
+
+        stack.push(Box::new(Probe::new("x", &log)), &mut context);
+        stack.push(Box::new(Probe::new("y", &log)), &mut context);
+        take(&log);
+        stack.clear(&mut context);
+        assert_eq!(take(&log), ["exit y", "exit x"]);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn overlays_draw_over_the_scenes_below_and_decide_on_systems() {
+        let log = Log::default();
+        let mut canvas = Canvas::new(1, 1);
+        let mut context = Context::default();
+        let mut stack = SceneStack::default();
+        assert!(!stack.runs_systems());
+
+        stack.push(Box::new(Probe::new("a", &log)), &mut context);
+        stack.push(Box::new(Probe::new("b", &log)), &mut context);
+        stack.push(Box::new(Probe::new("c", &log).overlay(true)), &mut context);
+        stack.push(Box::new(Probe::new("d", &log).overlay(false)), &mut context);
+        take(&log);
+
+        stack.draw(&mut canvas, &context);
+        assert_eq!(take(&log), ["draw b", "draw c", "draw d"]);
+        assert!(!stack.runs_systems());
+        stack.pop(&mut context);
+        assert!(stack.runs_systems());
+
+        // with nothing but overlays, all of them draw
+        let mut overlays = SceneStack::default();
+        overlays.push(Box::new(Probe::new("e", &log).overlay(false)), &mut context);
+        overlays.push(Box::new(Probe::new("f", &log).overlay(true)), &mut context);
+        take(&log);
+        overlays.draw(&mut canvas, &context);
+        assert_eq!(take(&log), ["draw e", "draw f"]);
+    }
+}
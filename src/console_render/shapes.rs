@@ -0,0 +1,323 @@
+//! Drawing primitives on top of [`Canvas::draw_cell`], which clips every cell.
+//! Shapes that cover an area only visit the part inside the canvas.
+
+use super::{
+    canvas::Canvas,
+    cell::{Cell, Style},
+};
+
+impl Canvas {
+    /// Draw a line between two points, both included (Bresenham). The line
+    /// is clipped to the canvas first, so far away ends cost nothing.
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, cell: impl Into<Cell>) {
+        let cell = cell.into();
+        let Some(((x0, y0), (x1, y1))) = self.clip_line((x0, y0), (x1, y1)) else {
+            return;
+        };
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y) = (x0, y0);
+        let mut err = dx + dy;
+
+        loop {
+            self.draw_cell(x, y, cell);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Draw the outline of a `width` by `height` rectangle.
+    pub fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, cell: impl Into<Cell>) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let cell = cell.into();
+        let (right, bottom) = (last(x, width), last(y, height));
+        if !self.overlaps((x, y), (right, bottom)) {
+            return;
+        }
+        self.line(x, y, right, y, cell);
+        self.line(x, bottom, right, bottom, cell);
+        self.line(x, y, x, bottom, cell);
+        self.line(right, y, right, bottom, cell);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, cell: impl Into<Cell>) {
+        let cell = cell.into();
+        let (x0, x1) = self.clip_x(x, last(x, width));
+        let (y0, y1) = self.clip_y(y, last(y, height));
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.draw_cell(x, y, cell);
+            }
+        }
+    }
+
+    pub fn circle(&mut self, cx: i32, cy: i32, radius: i32, cell: impl Into<Cell>) {
+        self.ellipse(cx, cy, radius, radius, cell);
+    }
+
+    /// Draw the outline of an axis-aligned ellipse. Every visible row gets
+    /// the points where the outline crosses it, and every visible column
+    /// too, which closes the gaps where the outline runs flat. The work
+    /// follows the canvas size, not the radii.
+    pub fn ellipse(&mut self, cx: i32, cy: i32, rx: i32, ry: i32, cell: impl Into<Cell>) {
+        if rx < 0 || ry < 0 {
+            return;
+        }
+        let cell = cell.into();
+        let (cx, cy, rx, ry) = (i64::from(cx), i64::from(cy), i64::from(rx), i64::from(ry));
+        let clamp = |v: i64| v.clamp(i32::MIN.into(), i32::MAX.into()) as i32;
+        let (left, top) = (clamp(cx - rx), clamp(cy - ry));
+        let (right, bottom) = (clamp(cx + rx), clamp(cy + ry));
+        if !self.overlaps((left, top), (right, bottom)) {
+            return;
+        }
+        let (x0, x1) = self.clip_x(left, right);
+        let (y0, y1) = self.clip_y(top, bottom);
+        let mut plot = |x: i64, y: i64| {
+            if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+                self.draw_cell(x, y, cell);
+            }
+        };
+
+        for y in y0..=y1 {
+            let x = half_width(rx, ry, i64::from(y) - cy);
+            plot(cx - x, i64::from(y));
+            plot(cx + x, i64::from(y));
+        }
+        for x in x0..=x1 {
+            let y = half_width(ry, rx, i64::from(x) - cx);
+            plot(i64::from(x), cy - y);
+            plot(i64::from(x), cy + y);
+        }
+    }
+
+    /// Fill the triangle between three points, either winding order.
+    pub fn fill_triangle(
+        &mut self,
+        a: (i32, i32),
+        b: (i32, i32),
+        c: (i32, i32),
+        cell: impl Into<Cell>,
+    ) {
+        let cell = cell.into();
+        let area = edge(a, b, c);
+        if area == 0 {
+            // degenerate, at least show where it is
+            self.line(a.0, a.1, b.0, b.1, cell);
+            self.line(b.0, b.1, c.0, c.1, cell);
+            return;
+        }
+
+        let (x0, x1) = self.clip_x(a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0));
+        let (y0, y1) = self.clip_y(a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1));
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let p = (x, y);
+                let (w0, w1, w2) = (edge(b, c, p), edge(c, a, p), edge(a, b, p));
+                // inside, or on an edge, for both windings
+                let inside = if area > 0 {
+                    w0 >= 0 && w1 >= 0 && w2 >= 0
+                } else {
+                    w0 <= 0 && w1 <= 0 && w2 <= 0
+                };
+                if inside {
+                    self.draw_cell(x, y, cell);
+                }
+            }
+        }
+    }
+
+    /// Draw text starting at `x`, `y`. A newline continues below `x`.
+    pub fn text(&mut self, x: i32, y: i32, text: &str, style: Style) {
+        let (mut cx, mut cy) = (x, y);
+        for c in text.chars() {
+            if c == '\n' {
+                cx = x;
+                cy = cy.saturating_add(1);
+                continue;
+            }
+            self.draw_cell(cx, cy, Cell::new(c, style));
+            cx = cx.saturating_add(1);
+        }
+    }
+
+    /// Clamp an inclusive column range to the canvas, empty if it misses.
+    fn clip_x(&self, from: i32, to: i32) -> (i32, i32) {
+        (from.max(0), to.min(self.width() as i32 - 1))
+    }
+
+    fn clip_y(&self, from: i32, to: i32) -> (i32, i32) {
+        (from.max(0), to.min(self.height() as i32 - 1))
+    }
+
+    /// Whether the box between two corners, both included, touches the canvas.
+    fn overlaps(&self, (left, top): (i32, i32), (right, bottom): (i32, i32)) -> bool {
+        let (x0, x1) = self.clip_x(left, right);
+        let (y0, y1) = self.clip_y(top, bottom);
+        x0 <= x1 && y0 <= y1
+    }
+
+    /// The part of the segment from `a` to `b` inside the canvas, with its
+    /// ends rounded to cells (Liang-Barsky). `None` if it misses.
+    fn clip_line(&self, a: (i32, i32), b: (i32, i32)) -> Option<((i32, i32), (i32, i32))> {
+        let (ax, ay) = (f64::from(a.0), f64::from(a.1));
+        let (dx, dy) = (f64::from(b.0) - ax, f64::from(b.1) - ay);
+        let (right, bottom) = (self.width() as f64 - 1.0, self.height() as f64 - 1.0);
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        // each edge as `p * t <= q`
+        for (p, q) in [(-dx, ax), (dx, right - ax), (-dy, ay), (dy, bottom - ay)] {
+            if p == 0.0 {
+                // parallel to the edge, and outside of it
+                if q < 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 {
+                return None;
+            }
+        }
+        let at = |t: f64| ((ax + t * dx).round() as i32, (ay + t * dy).round() as i32);
+        Some((at(t0), at(t1)))
+    }
+}
+
+/// The last of `len` cells starting at `start`, held within `i32`.
+fn last(start: i32, len: i32) -> i32 {
+    (i64::from(start) + i64::from(len) - 1).clamp(i32::MIN.into(), i32::MAX.into()) as i32
+}
+
+/// How far the outline of an ellipse with radii `r` across and `r_along`
+/// along an axis reaches across it, at `d` from the center along it.
+fn half_width(r: i64, r_along: i64, d: i64) -> i64 {
+    if r_along == 0 {
+        return r;
+    }
+    let t = d as f64 / r_along as f64;
+    (r as f64 * (1.0 - t * t).max(0.0).sqrt()).round() as i64
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`. Wide enough for
+/// any `i32` corners.
+fn edge(a: (i32, i32), b: (i32, i32), p: (i32, i32)) -> i128 {
+    let (ax, ay, bx, by, px, py) = (
+        i128::from(a.0),
+        i128::from(a.1),
+        i128::from(b.0),
+        i128::from(b.1),
+        i128::from(p.0),
+        i128::from(p.1),
+    );
+    (bx - ax) * (py - ay) - (by - ay) * (px - ax)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(canvas: &Canvas) -> Vec<String> {
+        (0..canvas.height() as i32)
+            .map(|y| {
+                (0..canvas.width() as i32)
+                    .map(|x| canvas.get(x, y).map_or('?', |cell| cell.ch))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn line_includes_both_ends() {
+        let mut canvas = Canvas::new(5, 3);
+        canvas.line(4, 2, 0, 0, '*');
+        assert_eq!(filled(&canvas), ["**   ", "  ** ", "    *"]);
+    }
+
+    #[test]
+    fn rect_is_clipped() {
+        let mut canvas = Canvas::new(4, 3);
+        canvas.rect(-1, 1, 4, 5, '#');
+        canvas.fill_rect(3, -10, 100, 100, '.');
+        assert_eq!(filled(&canvas), ["   .", "###.", "  #."]);
+    }
+
+    #[test]
+    fn circle_is_symmetric() {
+        let mut canvas = Canvas::new(5, 5);
+        canvas.circle(2, 2, 2, 'o');
+        assert_eq!(
+            filled(&canvas),
+            [" ooo ", "o   o", "o   o", "o   o", " ooo "]
+        );
+    }
+
+    #[test]
+    fn triangle_winding_does_not_matter() {
+        let mut a = Canvas::new(4, 4);
+        let mut b = Canvas::new(4, 4);
+        a.fill_triangle((0, 0), (3, 0), (0, 3), 'x');
+        b.fill_triangle((0, 0), (0, 3), (3, 0), 'x');
+        assert_eq!(filled(&a), ["xxxx", "xxx ", "xx  ", "x   "]);
+        assert_eq!(filled(&a), filled(&b));
+    }
+
+    #[test]
+    fn ellipse_outline_is_closed() {
+        let mut canvas = Canvas::new(9, 5);
+        canvas.ellipse(4, 2, 4, 2, 'o');
+        assert_eq!(
+            filled(&canvas),
+            [
+                "  ooooo  ",
+                " o     o ",
+                "o       o",
+                " o     o ",
+                "  ooooo  "
+            ]
+        );
+    }
+
+    #[test]
+    fn extreme_coordinates_are_clipped_without_overflow() {
+        let (min, max) = (i32::MIN, i32::MAX);
+        let mut canvas = Canvas::new(4, 3);
+        canvas.line(min, min, max, max, '\\');
+        canvas.line(max, 1, min, 1, '-');
+        canvas.fill_rect(-1, 2, max, 1, '_');
+        // all of these miss the canvas
+        canvas.line(min, min, min, max, '!');
+        canvas.rect(min, min, max, max, '!');
+        canvas.rect(max, max, max, max, '!');
+        canvas.fill_rect(max, max, max, max, '!');
+        canvas.circle(min, min, max, '!');
+        canvas.text(max, max, "far\naway", Style::new());
+        assert_eq!(filled(&canvas), ["\\   ", "----", "____"]);
+
+        let mut canvas = Canvas::new(5, 3);
+        canvas.rect(0, 0, max, max, '#');
+        // so flat that it is a straight line this close to its left end
+        canvas.ellipse(max, 1, max, 1, 'o');
+        assert_eq!(filled(&canvas), ["#####", "ooooo", "#    "]);
+
+        let mut canvas = Canvas::new(3, 3);
+        canvas.fill_triangle((min, min), (max, max), (min, max), 'x');
+        assert_eq!(filled(&canvas), ["x  ", "xx ", "xxx"]);
+    }
+}
@@ -0,0 +1,403 @@
+//! Multi-cell ASCII sprites loaded from text files, and an animation
+//! component that flips through their frames.
+//!
+//! A sprite file is an optional line of options followed by frames, each a
+//! `frame <milliseconds>` header and the art below it:
+//!
+//! ```text
+//! fg=bright_white transparent=.
+//! frame 150
+//! .\.
+//! =o>
+//! frame 150
+//! ...
+//! =o>
+//! ./.
+//! ```
+//!
+//! Options are `fg=<color>` and `bg=<color>`, written like in level files,
+//! and `transparent=<char>`, the character that lets whatever is below show
+//! through. It defaults to a space.
+
+use std::{path::Path, rc::Rc};
+
+use super::{
+    canvas::Canvas,
+    cell::{Cell, Style},
+    ecs::{Position, PreviousPosition},
+    load::{self, LoadError},
+    tilemap::{parse_color, Viewport},
+    Context, World,
+};
+
+/// One picture of a sprite and how long it shows when animated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteFrame {
+    width: usize,
+    height: usize,
+    /// Row by row, `None` for transparent cells.
+    cells: Vec<Option<Cell>>,
+    /// Seconds.
+    pub duration: f32,
+}
+
+impl SpriteFrame {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cell at a position in the frame, `None` where it is transparent.
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells[y * self.width + x]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sprite {
+    frames: Vec<SpriteFrame>,
+}
+
+impl Sprite {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        load::load(path, "sprite file", Self::parse)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LoadError> {
+        let mut style = Style::new();
+        let mut transparent = ' ';
+        // duration and rows of every frame
+        let mut frames: Vec<(f32, Vec<&str>)> = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |reason| LoadError::line(i, reason);
+            if let Some(ms) = line.strip_prefix("frame ") {
+                let ms: u32 = ms
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(format!("`{}` is not a duration in ms", ms.trim())))?;
+                frames.push((ms as f32 / 1000.0, Vec::new()));
+                continue;
+            }
+            match frames.last_mut() {
+                Some((_, rows)) => rows.push(line),
+                None if i == 0 => {
+                    for option in line.split_whitespace() {
+                        match option.split_once('=') {
+                            Some(("fg", color)) => {
+                                style = style.fg(parse_color(color).map_err(error)?)
+                            }
+                            Some(("bg", color)) => {
+                                style = style.bg(parse_color(color).map_err(error)?)
+                            }
+                            Some(("transparent", c)) if c.chars().count() == 1 => {
+                                transparent = c.chars().next().unwrap();
+                            }
+                            _ => return Err(error(format!("unknown sprite option `{}`", option))),
+                        }
+                    }
+                }
+                None if line.trim().is_empty() => {}
+                None => return Err(error("expected `frame <ms>`".to_owned())),
+            }
+        }
+
+        if frames.is_empty() {
+            return Err(LoadError::Incomplete("the sprite has no frames"));
+        }
+        let frames = frames
+            .into_iter()
+            .map(|(duration, mut rows)| {
+                // blank lines between frames are layout, not art
+                while rows.last().is_some_and(|row| row.trim().is_empty()) {
+                    rows.pop();
+                }
+                let width = rows
+                    .iter()
+                    .map(|row| row.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                let mut cells = vec![None; width * rows.len()];
+                for (y, row) in rows.iter().enumerate() {
+                    for (x, c) in row.chars().enumerate() {
+                        if c != transparent {
+                            cells[y * width + x] = Some(Cell::new(c, style));
+                        }
+                    }
+                }
+                SpriteFrame {
+                    width,
+                    height: rows.len(),
+                    cells,
+                    duration,
+                }
+            })
+            .collect();
+        Ok(Sprite { frames })
+    }
+
+    pub fn frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+}
+
+/// Which ways to mirror a sprite when drawing it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flip {
+    pub horizontal: bool,
+    pub vertical: bool,
+}
+
+impl Flip {
+    pub const NONE: Flip = Flip {
+        horizontal: false,
+        vertical: false,
+    };
+    pub const HORIZONTAL: Flip = Flip {
+        horizontal: true,
+        vertical: false,
+    };
+    pub const VERTICAL: Flip = Flip {
+        horizontal: false,
+        vertical: true,
+    };
+
+    /// The character that looks like `c` mirrored, so a `/` leaning one way
+    /// leans the other way after flipping.
+    fn mirror(self, c: char) -> char {
+        let c = if self.horizontal {
+            match c {
+                '/' => '\\',
+                '\\' => '/',
+                '(' => ')',
+                ')' => '(',
+                '[' => ']',
+                ']' => '[',
+                '{' => '}',
+                '}' => '{',
+                '<' => '>',
+                '>' => '<',
+                c => c,
+            }
+        } else {
+            c
+        };
+        if self.vertical {
+            match c {
+                '/' => '\\',
+                '\\' => '/',
+                '^' => 'v',
+                'v' => '^',
+                c => c,
+            }
+        } else {
+            c
+        }
+    }
+}
+
+impl Canvas {
+    /// Draw a sprite frame with its top-left corner at `x`, `y`, leaving the
+    /// cells under transparent parts as they are.
+    pub fn sprite(&mut self, x: i32, y: i32, frame: &SpriteFrame, flip: Flip) {
+        for fy in 0..frame.height {
+            for fx in 0..frame.width {
+                let Some(mut cell) = frame.get(fx, fy) else {
+                    continue;
+                };
+                cell.ch = flip.mirror(cell.ch);
+                let dx = if flip.horizontal {
+                    frame.width - 1 - fx
+                } else {
+                    fx
+                };
+                let dy = if flip.vertical {
+                    frame.height - 1 - fy
+                } else {
+                    fy
+                };
+                self.draw_cell(x + dx as i32, y + dy as i32, cell);
+            }
+        }
+    }
+}
+
+/// Plays a sprite's frames one after another, drawn at the entity's
+/// position instead of its [`Glyph`](super::Glyph).
+#[derive(Clone, Debug)]
+pub struct Animation {
+    /// Shared, many entities usually show the same sprite.
+    pub sprite: Rc<Sprite>,
+    pub flip: Flip,
+    /// Start over after the last frame instead of stopping on it.
+    pub looping: bool,
+    pub playing: bool,
+    frame: usize,
+    /// Seconds the current frame has been showing.
+    elapsed: f32,
+}
+
+impl Animation {
+    /// A looping animation that starts playing right away.
+    pub fn new(sprite: Rc<Sprite>) -> Self {
+        Self {
+            sprite,
+            flip: Flip::NONE,
+            looping: true,
+            playing: true,
+            frame: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Index of the frame showing.
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    pub fn frame(&self) -> &SpriteFrame {
+        &self.sprite.frames[self.frame]
+    }
+
+    /// Show a frame from its start.
+    pub fn set_frame(&mut self, frame: usize) {
+        self.frame = frame.min(self.sprite.frames.len() - 1);
+        self.elapsed = 0.0;
+    }
+
+    /// Whether a non-looping animation reached the end of its last frame.
+    pub fn is_finished(&self) -> bool {
+        !self.looping
+            && self.frame == self.sprite.frames.len() - 1
+            && self.elapsed >= self.frame().duration
+    }
+
+    /// Move on by `dt` seconds, skipping frames if it covers several.
+    pub fn advance(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+        self.elapsed += dt;
+        let last = self.sprite.frames.len() - 1;
+        loop {
+            let duration = self.frame().duration;
+            if self.elapsed < duration {
+                return;
+            }
+            if self.frame == last && !self.looping {
+                // hold the last frame
+                self.elapsed = duration;
+                return;
+            }
+            if duration <= 0.0 && self.frames_all_instant() {
+                return;
+            }
+            self.elapsed -= duration;
+            self.frame = if self.frame == last {
+                0
+            } else {
+                self.frame + 1
+            };
+        }
+    }
+
+    /// Zero length frames only, which would never settle on one.
+    fn frames_all_instant(&self) -> bool {
+        self.sprite.frames.iter().all(|frame| frame.duration <= 0.0)
+    }
+}
+
+/// Advance every animation by one tick.
+pub fn animate(context: &mut Context) {
+    let dt = context.time.dt;
+    for (_, animation) in context.world.write::<Animation>().iter_mut() {
+        animation.advance(dt);
+    }
+}
+
+/// Draw every entity that has a position and an animation, as seen through
+/// the viewport, `alpha` of the way through the tick.
+pub fn draw_animations(world: &World, canvas: &mut Canvas, viewport: Viewport, alpha: f32) {
+    let positions = world.read::<Position>();
+    let previous = world.read::<PreviousPosition>();
+    for (entity, animation) in world.read::<Animation>().iter() {
+        if let Some(position) = positions.get(entity) {
+            let position = position.interpolate(previous.get(entity), alpha);
+            let x = position.x.round() as i32 - viewport.x;
+            let y = position.y.round() as i32 - viewport.y;
+            canvas.sprite(x, y, animation.frame(), animation.flip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console_render::cell::Color;
+
+    const BIRD: &str = "\
+fg=red transparent=.
+frame 100
+.\\.
+=o>
+frame 200
+...
+=o>
+./.
+";
+
+    fn rows(canvas: &Canvas) -> Vec<String> {
+        canvas.to_text().lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn parses_frames_with_durations_and_transparency() {
+        let sprite = Sprite::parse(BIRD).unwrap();
+        let frames = sprite.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!((frames[0].width(), frames[0].height()), (3, 2));
+        assert_eq!((frames[1].width(), frames[1].height()), (3, 3));
+        assert_eq!(frames[1].duration, 0.2);
+        assert_eq!(frames[0].get(0, 0), None);
+        assert_eq!(frames[0].get(2, 1).unwrap().fg, Color::RED);
+
+        let error = Sprite::parse("frame soon\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: `soon` is not a duration in ms");
+    }
+
+    #[test]
+    fn draws_flipped_over_the_background() {
+        let sprite = Sprite::parse(BIRD).unwrap();
+        let mut canvas = Canvas::new(4, 3);
+        canvas.fill_rect(0, 0, 4, 3, ':');
+        canvas.sprite(1, 0, &sprite.frames()[1], Flip::HORIZONTAL);
+        assert_eq!(rows(&canvas), ["::::", ":<o=", "::\\:"]);
+    }
+
+    #[test]
+    fn animations_follow_frame_durations() {
+        let sprite = Rc::new(Sprite::parse(BIRD).unwrap());
+        let mut animation = Animation::new(sprite.clone());
+        animation.advance(0.05);
+        assert_eq!(animation.frame_index(), 0);
+        animation.advance(0.1);
+        assert_eq!(animation.frame_index(), 1);
+        // past the end of the second frame and halfway into the first
+        animation.advance(0.2);
+        assert_eq!(animation.frame_index(), 0);
+
+        let mut once = Animation {
+            looping: false,
+            ..Animation::new(sprite)
+        };
+        once.advance(1.0);
+        assert_eq!(once.frame_index(), 1);
+        assert!(once.is_finished());
+    }
+}
@@ -0,0 +1,180 @@
+//! Raw terminal mode that restores the terminal however the program ends.
+
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Once,
+    },
+};
+
+/// Switch to the alternate screen and hide the cursor.
+const ENTER: &str = "\x1B[?1049h\x1B[?25l";
+/// Show the cursor and go back to the main screen.
+const LEAVE: &str = "\x1B[0m\x1B[?25h\x1B[?1049l";
+
+/// Terminal settings from before raw mode, `None` while not in raw mode.
+#[cfg(unix)]
+static ORIGINAL: Mutex<Option<libc::termios>> = Mutex::new(None);
+#[cfg(not(unix))]
+static ORIGINAL: Mutex<Option<()>> = Mutex::new(None);
+
+static PANIC_HOOK: Once = Once::new();
+static SIGNALS: Once = Once::new();
+static TERMINATE: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+static RESIZED: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// Guard that keeps the terminal in raw mode: no line buffering, no echo, and
+/// Ctrl-C delivered as a key instead of a signal. Dropping it, panicking, or
+/// receiving SIGINT/SIGTERM/SIGHUP restores the original settings.
+pub struct RawMode {
+    _private: (),
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        PANIC_HOOK.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                restore();
+                previous(info);
+            }));
+        });
+        watch_signals();
+
+        let mut original = ORIGINAL.lock().unwrap();
+        if original.is_none() {
+            *original = Some(enter_raw_mode()?);
+        }
+        drop(original);
+
+        let mut stdout = io::stdout();
+        stdout.write_all(ENTER.as_bytes())?;
+        stdout.flush()?;
+
+        Ok(RawMode { _private: () })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Put the terminal back the way it was. Safe to call more than once.
+pub fn restore() {
+    // a poisoned lock still holds the settings we need to put back
+    let mut original = ORIGINAL.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(settings) = original.take() {
+        leave_raw_mode(settings);
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(LEAVE.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// Whether SIGINT, SIGTERM or SIGHUP arrived since raw mode was enabled.
+pub fn termination_requested() -> bool {
+    TERMINATE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// Whether the terminal was resized since the last call.
+pub fn take_resized() -> bool {
+    RESIZED
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|flag| flag.swap(false, Ordering::Relaxed))
+}
+
+/// Columns and rows of the terminal, `None` if stdout is not a terminal.
+#[cfg(unix)]
+pub fn size() -> Option<(usize, usize)> {
+    // SAFETY: TIOCGWINSZ only writes the winsize struct we own.
+    let size = unsafe {
+        let mut size = std::mem::zeroed::<libc::winsize>();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) != 0 {
+            return None;
+        }
+        size
+    };
+    if size.ws_col == 0 || size.ws_row == 0 {
+        return None;
+    }
+    Some((size.ws_col as usize, size.ws_row as usize))
+}
+
+#[cfg(not(unix))]
+pub fn size() -> Option<(usize, usize)> {
+    None
+}
+
+#[cfg(unix)]
+fn watch_signals() {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGWINCH};
+
+    SIGNALS.call_once(|| {
+        let flag = Arc::new(AtomicBool::new(false));
+        for signal in [SIGINT, SIGTERM, SIGHUP] {
+            // a second signal kills the process even if the engine is stuck,
+            // the first one lets it shut down and restore the terminal
+            let _ = signal_hook::flag::register_conditional_shutdown(signal, 1, flag.clone());
+            let _ = signal_hook::flag::register(signal, flag.clone());
+        }
+        *TERMINATE.lock().unwrap() = Some(flag);
+
+        let resized = Arc::new(AtomicBool::new(false));
+        let _ = signal_hook::flag::register(SIGWINCH, resized.clone());
+        *RESIZED.lock().unwrap() = Some(resized);
+    });
+}
+
+#[cfg(not(unix))]
+fn watch_signals() {
+    SIGNALS.call_once(|| {});
+}
+
+#[cfg(unix)]
+fn enter_raw_mode() -> io::Result<libc::termios> {
+    // SAFETY: tcgetattr/tcsetattr only read and write the termios struct we own.
+    unsafe {
+        let mut original = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut raw = original;
+        raw.c_iflag &= !(libc::BRKINT | libc::ICRNL | libc::INPCK | libc::ISTRIP | libc::IXON);
+        raw.c_lflag &= !(libc::ECHO | libc::ICANON | libc::IEXTEN | libc::ISIG);
+        raw.c_cflag |= libc::CS8;
+        // block until at least one byte is available
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+
+        if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(original)
+    }
+}
+
+#[cfg(unix)]
+fn leave_raw_mode(original: libc::termios) {
+    // SAFETY: see `enter_raw_mode`.
+    unsafe {
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &original);
+    }
+}
+
+#[cfg(not(unix))]
+fn enter_raw_mode() -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn leave_raw_mode(_original: ()) {}
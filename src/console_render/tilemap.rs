@@ -0,0 +1,351 @@
+//! Levels loaded from plain-text map files.
+//!
+//! A map file has a `legend:` section describing every tile character and a
+//! `map:` section with one character per tile:
+//!
+//! ```text
+//! legend:
+//! # wall solid fg=white bg=bright_black
+//! . floor fg=bright_black
+//! ~ water solid fg=blue glyph=≈
+//! @ floor spawn glyph=.
+//!
+//! map:
+//! ##########
+//! #..@...~~#
+//! ##########
+//! ```
+//!
+//! A legend line is the character, a tile name, then any of `solid`,
+//! `spawn` (where the player starts), `fg=<color>`, `bg=<color>` and
+//! `glyph=<char>` (what to draw instead of the map character). Colors are
+//! ANSI names like `red` or `bright_blue`, palette indices `0`-`255`, or
+//! `#rrggbb`. Spaces in the map are empty, non-solid tiles that draw nothing.
+
+use std::path::Path;
+
+use super::{
+    canvas::Canvas,
+    cell::{Cell, Color, Style},
+    ecs::{Entity, Position},
+    load::{self, LoadError},
+    Context,
+};
+
+/// One kind of tile from the legend.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileType {
+    pub name: String,
+    pub cell: Cell,
+    pub solid: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Tilemap {
+    width: usize,
+    height: usize,
+    /// Index into `types` for every tile, `None` for empty ones.
+    tiles: Vec<Option<usize>>,
+    types: Vec<TileType>,
+    spawn: Option<(i32, i32)>,
+}
+
+enum Section {
+    None,
+    Legend,
+    Map,
+}
+
+impl Tilemap {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        load::load(path, "map file", Self::parse)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LoadError> {
+        let mut section = Section::None;
+        let mut legend: Vec<(char, TileType, bool)> = Vec::new();
+        let mut rows: Vec<(usize, &str)> = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let number = i + 1;
+            match line.trim_end() {
+                "legend:" => section = Section::Legend,
+                "map:" => section = Section::Map,
+                line => match section {
+                    Section::None if line.is_empty() => {}
+                    Section::None => {
+                        return Err(LoadError::line(
+                            i,
+                            "expected `legend:` or `map:` first".to_owned(),
+                        ))
+                    }
+                    Section::Legend if line.is_empty() => {}
+                    Section::Legend => {
+                        let error = |reason| LoadError::line(i, reason);
+                        let entry = parse_legend(line).map_err(error)?;
+                        if legend.iter().any(|(tile, ..)| *tile == entry.0) {
+                            return Err(error(format!("`{}` is already in the legend", entry.0)));
+                        }
+                        legend.push(entry);
+                    }
+                    Section::Map => rows.push((number, line)),
+                },
+            }
+        }
+
+        // blank lines around the map are layout, not tiles
+        while rows.last().is_some_and(|(_, row)| row.is_empty()) {
+            rows.pop();
+        }
+        let first = rows.iter().position(|(_, row)| !row.is_empty());
+        let rows = &rows[first.ok_or(LoadError::Incomplete("the map section is empty"))?..];
+
+        let width = rows
+            .iter()
+            .map(|(_, row)| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let height = rows.len();
+        let mut tiles = vec![None; width * height];
+        let mut spawn = None;
+
+        for (y, (number, row)) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let index = legend
+                    .iter()
+                    .position(|(tile, ..)| *tile == c)
+                    .ok_or_else(|| LoadError::Line {
+                        line: *number,
+                        reason: format!("`{}` is not in the legend", c),
+                    })?;
+                if legend[index].2 {
+                    spawn = Some((x as i32, y as i32));
+                }
+                tiles[y * width + x] = Some(index);
+            }
+        }
+
+        Ok(Tilemap {
+            width,
+            height,
+            tiles,
+            types: legend.into_iter().map(|(_, tile, _)| tile).collect(),
+            spawn,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Where the legend's `spawn` tile is, the last one if there are several.
+    pub fn spawn(&self) -> Option<(i32, i32)> {
+        self.spawn
+    }
+
+    /// The tile at a map position, `None` for empty tiles and outside the map.
+    pub fn get(&self, x: i32, y: i32) -> Option<&TileType> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        let index = self.tiles[y as usize * self.width + x as usize]?;
+        Some(&self.types[index])
+    }
+
+    /// Whether a tile blocks movement. Outside the map counts as solid.
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        let inside = x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height;
+        !inside || self.get(x, y).is_some_and(|tile| tile.solid)
+    }
+
+    /// Draw the part of the map the viewport shows.
+    pub fn draw(&self, canvas: &mut Canvas, viewport: Viewport) {
+        for sy in 0..canvas.height() as i32 {
+            for sx in 0..canvas.width() as i32 {
+                if let Some(tile) = self.get(sx + viewport.x, sy + viewport.y) {
+                    canvas.draw_cell(sx, sy, tile.cell);
+                }
+            }
+        }
+    }
+}
+
+/// Parse `<char> <name> [options]`, returns the character, the tile and
+/// whether it is the spawn point.
+fn parse_legend(line: &str) -> Result<(char, TileType, bool), String> {
+    let mut chars = line.chars();
+    let tile = chars.next().ok_or("empty legend entry")?;
+    let mut words = chars.as_str().split_whitespace();
+    let name = words
+        .next()
+        .ok_or_else(|| format!("`{}` needs a tile name", tile))?;
+
+    let mut style = Style::new();
+    let mut glyph = tile;
+    let (mut solid, mut spawn) = (false, false);
+    for word in words {
+        match word.split_once('=') {
+            None if word == "solid" => solid = true,
+            None if word == "spawn" => spawn = true,
+            Some(("fg", color)) => style = style.fg(parse_color(color)?),
+            Some(("bg", color)) => style = style.bg(parse_color(color)?),
+            Some(("glyph", c)) => {
+                let mut chars = c.chars();
+                glyph = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => return Err(format!("glyph `{}` is not a single character", c)),
+                };
+            }
+            _ => return Err(format!("unknown tile option `{}`", word)),
+        }
+    }
+
+    let tile_type = TileType {
+        name: name.to_owned(),
+        cell: Cell::new(glyph, style),
+        solid,
+    };
+    Ok((tile, tile_type, spawn))
+}
+
+pub(super) fn parse_color(text: &str) -> Result<Color, String> {
+    const NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+
+    if let Some(hex) = text.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)
+            .ok_or_else(|| format!("`{}` is not a #rrggbb color", text))?;
+        return Ok(Color::Rgb(
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ));
+    }
+    if let Ok(index) = text.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+    let (bright, name) = match text.strip_prefix("bright_") {
+        Some(name) => (8, name),
+        None => (0, text),
+    };
+    NAMES
+        .iter()
+        .position(|n| *n == name)
+        .map(|n| Color::Ansi(n as u8 + bright))
+        .ok_or_else(|| format!("unknown color `{}`", text))
+}
+
+/// The top-left map position shown on the canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Scroll the viewport to keep the player centered, without showing past
+/// the edges of a map larger than the canvas. A smaller map stays centered.
+pub fn follow_player(context: &mut Context) {
+    let Some(position) = context
+        .player
+        .and_then(|player| context.world.get::<Position>(player))
+    else {
+        return;
+    };
+    let (width, height) = (context.width as i32, context.height as i32);
+    let mut x = position.x.round() as i32 - width / 2;
+    let mut y = position.y.round() as i32 - height / 2;
+
+    if let Some(map) = &context.tilemap {
+        let (map_w, map_h) = (map.width() as i32, map.height() as i32);
+        x = if map_w <= width {
+            (map_w - width) / 2
+        } else {
+            x.clamp(0, map_w - width)
+        };
+        y = if map_h <= height {
+            (map_h - height) / 2
+        } else {
+            y.clamp(0, map_h - height)
+        };
+    }
+    context.viewport = Viewport { x, y };
+}
+
+/// Move an entity by whole tiles unless a solid tile is in the way, or the
+/// edge of the canvas when there is no level. Returns whether it moved.
+pub fn try_move(context: &mut Context, entity: Entity, dx: i32, dy: i32) -> bool {
+    let mut positions = context.world.write::<Position>();
+    let Some(position) = positions.get_mut(entity) else {
+        return false;
+    };
+    let x = position.x.round() as i32 + dx;
+    let y = position.y.round() as i32 + dy;
+    let blocked = match &context.tilemap {
+        Some(map) => map.is_solid(x, y),
+        None => x < 0 || y < 0 || x >= context.width as i32 || y >= context.height as i32,
+    };
+    if blocked {
+        return false;
+    }
+    position.x = x as f32;
+    position.y = y as f32;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "\
+legend:
+# wall solid fg=white
+. floor fg=8
+@ floor spawn glyph=.
+
+map:
+#####
+#.@ #
+#####
+";
+
+    #[test]
+    fn parses_legend_and_map() {
+        let map = Tilemap::parse(LEVEL).unwrap();
+        assert_eq!((map.width(), map.height()), (5, 3));
+        assert_eq!(map.spawn(), Some((2, 1)));
+        assert_eq!(map.get(2, 1).unwrap().cell.ch, '.');
+        assert_eq!(map.get(1, 1).unwrap().cell.fg, Color::Indexed(8));
+        assert_eq!(map.get(0, 0).unwrap().cell.fg, Color::WHITE);
+        assert!(map.get(3, 1).is_none());
+    }
+
+    #[test]
+    fn solid_tiles_and_outside_block() {
+        let map = Tilemap::parse(LEVEL).unwrap();
+        assert!(map.is_solid(0, 1));
+        assert!(!map.is_solid(1, 1));
+        assert!(!map.is_solid(3, 1));
+        assert!(map.is_solid(-1, 1));
+        assert!(map.is_solid(2, 3));
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        let error = Tilemap::parse("legend:\n# wall\nmap:\n#x#\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4: `x` is not in the legend");
+        let error = Tilemap::parse("legend:\n# wall fg=mauve\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown color `mauve`");
+        let error = Tilemap::parse("legend:\n# wall\n. floor\n# floor\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 4: `#` is already in the legend");
+    }
+}
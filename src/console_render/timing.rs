@@ -0,0 +1,226 @@
+//! Fixed-timestep game loop timing: logic runs at a steady tick rate while
+//! rendering happens as often as the frame cap allows.
+
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// How often logic ticks and frames render.
+#[derive(Clone, Copy, Debug)]
+pub struct Timestep {
+    /// Logic ticks per second.
+    pub tick_rate: u32,
+    /// Frames per second to render at most, `None` renders as fast as possible.
+    pub max_fps: Option<u32>,
+    /// Ticks to catch up on in one frame at most. When the game falls further
+    /// behind, the rest is dropped and it slows down instead of spiralling.
+    /// Taken as 1 when 0, which would never tick.
+    pub max_ticks_per_frame: u32,
+}
+
+impl Default for Timestep {
+    fn default() -> Self {
+        Self {
+            tick_rate: 30,
+            max_fps: Some(60),
+            max_ticks_per_frame: 5,
+        }
+    }
+}
+
+/// Timing information available to the game.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Time {
+    /// Ticks run since the engine started.
+    pub tick: u64,
+    /// Seconds of game time per tick.
+    pub dt: f32,
+    /// How far between the last tick and the next one this frame renders,
+    /// from 0 to 1, for interpolating positions.
+    pub alpha: f32,
+    /// Measured frames per second.
+    pub fps: f32,
+    /// Measured ticks per second, below the tick rate when frames are skipped.
+    pub tick_rate: f32,
+}
+
+/// Time that only moves when advanced, so tests can run the engine frame by
+/// frame, see [`Engine::set_manual_time`](super::Engine::set_manual_time).
+/// Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct ManualTime(Rc<Cell<Duration>>);
+
+impl ManualTime {
+    pub fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+
+    /// Time advanced so far.
+    pub fn elapsed(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+pub(crate) struct Clock {
+    step: Timestep,
+    /// Read instead of the system clock when set.
+    manual: Option<ManualTime>,
+    /// When the clock was created, manual time counts from here.
+    origin: Instant,
+    tick_duration: Duration,
+    frame_start: Instant,
+    accumulator: Duration,
+    window_start: Instant,
+    frames: u32,
+    ticks: u32,
+}
+
+impl Clock {
+    const WINDOW: Duration = Duration::from_millis(500);
+
+    pub fn new(step: Timestep, manual: Option<ManualTime>) -> Self {
+        let origin = Instant::now();
+        let now = origin + manual.as_ref().map_or(Duration::ZERO, ManualTime::elapsed);
+        Self {
+            step: Timestep {
+                max_ticks_per_frame: step.max_ticks_per_frame.max(1),
+                ..step
+            },
+            manual,
+            origin,
+            tick_duration: Duration::from_secs(1) / step.tick_rate.max(1),
+            frame_start: now,
+            accumulator: Duration::ZERO,
+            window_start: now,
+            frames: 0,
+            ticks: 0,
+        }
+    }
+
+    pub fn timestep(&self) -> Timestep {
+        self.step
+    }
+
+    pub fn manual_time(&self) -> Option<ManualTime> {
+        self.manual.clone()
+    }
+
+    fn now(&self) -> Instant {
+        match &self.manual {
+            Some(time) => self.origin + time.elapsed(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Start a frame, returns how many ticks to run before rendering it.
+    pub fn begin_frame(&mut self, time: &mut Time) -> u32 {
+        let now = self.now();
+        // saturating: the clock can appear to go backwards on some platforms
+        self.accumulator += now.saturating_duration_since(self.frame_start);
+        self.frame_start = now;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.tick_duration && ticks < self.step.max_ticks_per_frame {
+            self.accumulator -= self.tick_duration;
+            ticks += 1;
+        }
+        if self.accumulator >= self.tick_duration {
+            // too far behind, skip ahead keeping only the partial tick
+            let nanos = self.accumulator.as_nanos() % self.tick_duration.as_nanos();
+            self.accumulator = Duration::from_nanos(nanos as u64);
+        }
+
+        self.frames += 1;
+        self.ticks += ticks;
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= Self::WINDOW {
+            time.fps = self.frames as f32 / elapsed.as_secs_f32();
+            time.tick_rate = self.ticks as f32 / elapsed.as_secs_f32();
+            self.frames = 0;
+            self.ticks = 0;
+            self.window_start = now;
+        }
+
+        time.dt = self.tick_duration.as_secs_f32();
+        time.alpha = self.accumulator.as_secs_f32() / time.dt;
+        ticks
+    }
+
+    /// Sleep out the rest of the frame when the frame rate is capped. Manual
+    /// time never sleeps.
+    pub fn end_frame(&self) {
+        let (Some(max_fps), None) = (self.step.max_fps, &self.manual) else {
+            return;
+        };
+        let deadline = self.frame_start + Duration::from_secs(1) / max_fps.max(1);
+        // no underflow when the frame overran, there is just nothing to wait for
+        if let Some(wait) = deadline.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch_up_is_capped() {
+        let step = Timestep {
+            tick_rate: 100,
+            max_fps: None,
+            max_ticks_per_frame: 3,
+        };
+        let mut clock = Clock::new(step, None);
+        let mut time = Time::default();
+        // pretend the game stalled for a second
+        clock.frame_start -= Duration::from_secs(1);
+        assert_eq!(clock.begin_frame(&mut time), 3);
+        assert!(clock.accumulator < clock.tick_duration);
+        assert!((0.0..1.0).contains(&time.alpha));
+    }
+
+    #[test]
+    fn zero_catch_up_still_ticks() {
+        let mut clock = Clock::new(
+            Timestep {
+                tick_rate: 100,
+                max_fps: None,
+                max_ticks_per_frame: 0,
+            },
+            None,
+        );
+        let mut time = Time::default();
+        clock.frame_start -= Duration::from_secs(1);
+        assert_eq!(clock.begin_frame(&mut time), 1);
+    }
+
+    #[test]
+    fn overrun_frames_do_not_panic() {
+        let mut clock = Clock::new(
+            Timestep {
+                max_fps: Some(1000),
+                ..Timestep::default()
+            },
+            None,
+        );
+        clock.frame_start -= Duration::from_secs(1);
+        clock.end_frame();
+    }
+
+    #[test]
+    fn manual_time_ticks_only_when_advanced() {
+        let manual = ManualTime::default();
+        let mut clock = Clock::new(Timestep::default(), Some(manual.clone()));
+        let mut time = Time::default();
+        assert_eq!(clock.begin_frame(&mut time), 0);
+        manual.advance(Duration::from_millis(50));
+        assert_eq!(clock.begin_frame(&mut time), 1);
+        assert!((time.alpha - 0.5).abs() < 1e-3);
+        // capped at 60 fps, but nothing to sleep for
+        clock.end_frame();
+        assert_eq!(clock.begin_frame(&mut time), 0);
+    }
+}
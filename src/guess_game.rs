@@ -1,7 +1,7 @@
-use std::io::{self, Write};
 use rand::Rng;
+use std::io::{self, Write};
 
-pub(crate) fn run() {
+pub fn run() {
     println!("Guess the number (1 to 100)!");
 
     // use rand to generate random number
@@ -39,4 +39,4 @@ pub(crate) fn run() {
             }
         }
     }
-}
\ No newline at end of file
+}
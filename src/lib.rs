@@ -0,0 +1,7 @@
+pub mod adapter;
+pub mod app;
+pub mod camera;
+pub mod console_render;
+pub mod guess_game;
+pub mod text;
+pub mod ui;
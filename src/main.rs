@@ -1,5 +1,9 @@
-mod App;
+use std::error::Error;
 
+use sketch::{
+    adapter::{self, AdapterChoice, AdapterOptions},
+    app::{self, AppError},
+};
 use wgpu::SurfaceError;
 use winit::{
     dpi::LogicalSize,
@@ -8,11 +12,67 @@ use winit::{
     window::WindowBuilder,
 };
 
+const USAGE: &str = "\
+usage: sketch [options]
+
+options:
+    --list-adapters         print every adapter on the selected backends and exit
+    --adapter <index|name>  use the adapter at <index> in the list, or the first
+                            one whose name contains <name> (e.g. llvmpipe)
+    --backend <backends>    comma separated subset of vulkan, gl, metal, dx12,
+                            dx11, webgpu, primary, secondary, all
+    -h, --help              print this help and exit";
+
+/// Command line options.
+#[derive(Default)]
+struct Options {
+    list_adapters: bool,
+    adapter: AdapterOptions,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--list-adapters" => options.list_adapters = true,
+                "--adapter" => {
+                    let value = args.next().ok_or("`--adapter` expects a value")?;
+                    options.adapter.choice = Some(AdapterChoice::parse(&value));
+                }
+                "--backend" => {
+                    let value = args.next().ok_or("`--backend` expects a value")?;
+                    options.adapter.backends = adapter::parse_backends(&value)?;
+                }
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
 fn main() {
-    futures::executor::block_on(run());
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if options.list_adapters {
+        adapter::list(options.adapter.backends);
+        return;
+    }
+
+    run(options);
 }
 
-async fn run() {
+fn run(options: Options) {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("Sketch")
@@ -20,7 +80,13 @@ async fn run() {
         .build(&event_loop)
         .unwrap();
 
-    let mut app = App::App::new(window).await;
+    let mut app = match futures::executor::block_on(app::App::new(window, &options.adapter)) {
+        Ok(app) => app,
+        Err(err) => {
+            report(&err);
+            std::process::exit(1);
+        }
+    };
 
     event_loop.run(move |event, _, control_flow| {
         control_flow.set_poll();
@@ -29,46 +95,112 @@ async fn run() {
             winit::event::Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == app.window().id() => {
-                if !app.input(event) {
-                    match event {
-                        WindowEvent::Resized(size) => {
-                            app.resize(*size);
-                        }
-                        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                            app.resize(**new_inner_size);
-                        }
-                        WindowEvent::CloseRequested
-                        | WindowEvent::KeyboardInput {
-                            input:
-                                KeyboardInput {
-                                    state: winit::event::ElementState::Pressed,
-                                    virtual_keycode: Some(VirtualKeyCode::Escape),
-                                    ..
-                                },
+            } if window_id == app.window().id() && !app.input(event) => match event {
+                WindowEvent::Resized(size) => {
+                    app.resize(*size);
+                }
+                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    app.resize(**new_inner_size);
+                }
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: winit::event::ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
                             ..
-                        } => {
-                            control_flow.set_exit();
-                        }
-                        _ => (),
-                    }
+                        },
+                    ..
+                } => {
+                    control_flow.set_exit();
                 }
-            }
+                _ => (),
+            },
             winit::event::Event::MainEventsCleared => {
                 app.window().request_redraw();
             }
-            Event::RedrawRequested(window_id) => {
-                if window_id == app.window().id() {
-                    app.update();
-                    match app.render() {
-                        Ok(_) => {}
-                        Err(SurfaceError::Lost) => app.resize(app.size),
-                        Err(SurfaceError::OutOfMemory) => control_flow.set_exit(),
-                        Err(e) => eprintln!("error: {:?}", e),
+            Event::RedrawRequested(window_id) if window_id == app.window().id() => {
+                if app.is_device_lost() {
+                    eprintln!("warning: device lost, recreating gpu resources");
+                    if let Err(err) = futures::executor::block_on(app.recover()) {
+                        report(&err);
+                        control_flow.set_exit_with_code(1);
+                        return;
                     }
                 }
+
+                app.update();
+                match app.render() {
+                    Ok(_) => {}
+                    // reconfigure the surface, the next frame will pick it up
+                    Err(SurfaceError::Lost | SurfaceError::Outdated) => app.resize(app.size),
+                    Err(SurfaceError::Timeout) => eprintln!("warning: surface timed out"),
+                    Err(SurfaceError::OutOfMemory) => control_flow.set_exit(),
+                }
             }
             _ => (),
         }
     });
 }
+
+/// Print a human readable diagnostic for a failed startup.
+fn report(err: &AppError) {
+    eprintln!("error: {}", err);
+
+    let mut source = err.source();
+    while let Some(cause) = source {
+        eprintln!("  caused by: {}", cause);
+        source = cause.source();
+    }
+
+    if let Some(info) = err.adapter_info() {
+        eprintln!();
+        eprintln!("adapter:");
+        eprintln!("  name:        {}", info.name);
+        eprintln!("  backend:     {:?}", info.backend);
+        eprintln!("  device type: {:?}", info.device_type);
+        eprintln!("  driver:      {} {}", info.driver, info.driver_info);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::Backends;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_adapter_flags() {
+        let options = parse(&["--adapter", "llvm", "--backend", "vulkan,gl"]).unwrap();
+        assert!(!options.list_adapters);
+        assert_eq!(
+            options.adapter.choice,
+            Some(AdapterChoice::Name("llvm".to_owned()))
+        );
+        assert_eq!(options.adapter.backends, Backends::VULKAN | Backends::GL);
+
+        let options = parse(&["--list-adapters", "--adapter", "0"]).unwrap();
+        assert!(options.list_adapters);
+        assert_eq!(options.adapter.choice, Some(AdapterChoice::Index(0)));
+        assert_eq!(options.adapter.backends, Backends::all());
+    }
+
+    #[test]
+    fn reports_bad_arguments() {
+        assert_eq!(
+            parse(&["--adapter"]).err().unwrap(),
+            "`--adapter` expects a value"
+        );
+        assert_eq!(
+            parse(&["--backend", "gl,glide"]).err().unwrap(),
+            "unknown backend `glide`"
+        );
+        assert_eq!(
+            parse(&["--fullscreen"]).err().unwrap(),
+            "unknown argument `--fullscreen`"
+        );
+    }
+}
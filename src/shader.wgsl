@@ -1,3 +1,15 @@
+struct Camera {
+  view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var diffuse_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var diffuse_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> camera: Camera;
+
 struct VertexInput {
   @location(0) position: vec3<f32>,
   @location(1) color: vec3<f32>
@@ -5,13 +17,16 @@ struct VertexInput {
 
 struct VertexOutput {
   @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
   @location(1) color: vec3<f32>
 }
 
 @vertex
 fn vert_main(model: VertexInput) -> VertexOutput {
   var out: VertexOutput;
-  out.position = vec4<f32>(model.position, 1.0);
+  out.position = camera.view_proj * vec4<f32>(model.position, 1.0);
+  // the scene spans [-0.5, 0.5], map it onto the whole texture
+  out.uv = vec2<f32>(model.position.x + 0.5, 0.5 - model.position.y);
   out.color = model.color;
   return out;
 }
@@ -20,3 +35,8 @@ fn vert_main(model: VertexInput) -> VertexOutput {
 fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
   return vec4<f32>(in.color, 1.0);
 }
+
+@fragment
+fn frag_texture(in: VertexOutput) -> @location(0) vec4<f32> {
+  return textureSample(diffuse_texture, diffuse_sampler, in.uv);
+}
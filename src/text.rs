@@ -0,0 +1,741 @@
+use std::{collections::HashMap, mem::size_of};
+
+use bytemuck::{cast_slice, Pod, Zeroable};
+use fontdue::{Font, FontSettings, Metrics};
+use wgpu::{
+    include_wgsl, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BlendState,
+    BufferAddress, BufferDescriptor, BufferUsages, ColorWrites, Extent3d, FragmentState,
+    MultisampleState, PipelineLayoutDescriptor, PrimitiveState, RenderPass,
+    RenderPipelineDescriptor, ShaderStages, TextureAspect, TextureDescriptor, TextureUsages,
+    VertexAttribute, VertexBufferLayout, VertexState, VertexStepMode,
+};
+use winit::dpi::PhysicalSize;
+
+use crate::app::AppError;
+
+/// Width and height of the glyph atlas texture in pixels.
+const ATLAS_SIZE: u32 = 1024;
+/// Empty pixels kept between glyphs so linear filtering doesn't bleed.
+const GLYPH_PADDING: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle {
+    /// Font size in physical pixels.
+    pub size: f32,
+    pub color: [f32; 4],
+    pub align: Align,
+    /// Wrap lines at word boundaries once they get wider than this.
+    pub max_width: Option<f32>,
+    /// Multiplier on the font's own line height.
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: Align::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct TextVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+impl TextVertex {
+    fn desc<'a>() -> VertexBufferLayout<'a> {
+        VertexBufferLayout {
+            array_stride: size_of::<TextVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                    shader_location: 0,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 2]>() as BufferAddress,
+                    format: wgpu::VertexFormat::Float32x2,
+                    shader_location: 1,
+                },
+                VertexAttribute {
+                    offset: (size_of::<[f32; 2]>() * 2) as BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                    shader_location: 2,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    ch: char,
+    px: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Glyph {
+    metrics: Metrics,
+    /// `[min_u, min_v, max_u, max_v]` inside the atlas.
+    uv: [f32; 4],
+}
+
+/// CPU-side glyph cache. Glyphs are rasterized on first use and packed into
+/// rows ("shelves") of a single channel bitmap that mirrors the atlas texture.
+struct GlyphAtlas {
+    pixels: Vec<u8>,
+    /// `None` for glyphs that did not fit, so they aren't rasterized again.
+    glyphs: HashMap<GlyphKey, Option<Glyph>>,
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+    /// Rows touched since the last upload.
+    dirty: Option<(u32, u32)>,
+    /// Set once a glyph did not fit, to warn only the first time.
+    full: bool,
+}
+
+impl GlyphAtlas {
+    fn new() -> Self {
+        Self {
+            pixels: vec![0; (ATLAS_SIZE * ATLAS_SIZE) as usize],
+            glyphs: HashMap::new(),
+            cursor_x: 0,
+            cursor_y: 0,
+            row_height: 0,
+            dirty: None,
+            full: false,
+        }
+    }
+
+    fn glyph(&mut self, font: &Font, ch: char, px: u32) -> Option<Glyph> {
+        let key = GlyphKey { ch, px };
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return *glyph;
+        }
+
+        let (metrics, bitmap) = font.rasterize(ch, px as f32);
+        let (width, height) = (metrics.width as u32, metrics.height as u32);
+        let Some((x, y)) = self.allocate(width, height) else {
+            if !self.full {
+                eprintln!(
+                    "warning: glyph atlas is full, dropping `{}` and later glyphs",
+                    ch
+                );
+                self.full = true;
+            }
+            self.glyphs.insert(key, None);
+            return None;
+        };
+
+        for row in 0..height {
+            let src = (row * width) as usize;
+            let dst = ((y + row) * ATLAS_SIZE + x) as usize;
+            self.pixels[dst..dst + width as usize]
+                .copy_from_slice(&bitmap[src..src + width as usize]);
+        }
+        self.mark_dirty(y, y + height);
+
+        let size = ATLAS_SIZE as f32;
+        let glyph = Glyph {
+            metrics,
+            uv: [
+                x as f32 / size,
+                y as f32 / size,
+                (x + width) as f32 / size,
+                (y + height) as f32 / size,
+            ],
+        };
+        self.glyphs.insert(key, Some(glyph));
+        Some(glyph)
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if self.cursor_x + width > ATLAS_SIZE {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height + GLYPH_PADDING;
+            self.row_height = 0;
+        }
+        if width > ATLAS_SIZE || self.cursor_y + height > ATLAS_SIZE {
+            return None;
+        }
+
+        let position = (self.cursor_x, self.cursor_y);
+        self.cursor_x += width + GLYPH_PADDING;
+        self.row_height = self.row_height.max(height);
+        Some(position)
+    }
+
+    fn mark_dirty(&mut self, start: u32, end: u32) {
+        self.dirty = Some(match self.dirty {
+            Some((s, e)) => (s.min(start), e.max(end)),
+            None => (start, end),
+        });
+    }
+}
+
+/// A line of laid out text, glyph positions are relative to the line start.
+#[derive(Default)]
+struct Line {
+    glyphs: Vec<(char, f32)>,
+    width: f32,
+}
+
+/// Lays out strings with the bundled font and batches them into glyph quads
+/// for the [`TextPipeline`].
+pub struct Text {
+    font: Font,
+    atlas: GlyphAtlas,
+    vertices: Vec<TextVertex>,
+}
+
+impl Text {
+    pub fn new() -> Result<Self, AppError> {
+        let font_bytes = include_bytes!("../assets/fonts/DejaVuSans.ttf") as &[u8];
+        let font = Font::from_bytes(font_bytes, FontSettings::default()).map_err(|reason| {
+            AppError::LoadFont {
+                name: "DejaVuSans.ttf",
+                reason,
+            }
+        })?;
+
+        Ok(Self {
+            font,
+            atlas: GlyphAtlas::new(),
+            vertices: Vec::new(),
+        })
+    }
+
+    /// Number of glyphs rasterized into the atlas so far.
+    pub fn cached_glyphs(&self) -> usize {
+        self.atlas.glyphs.values().flatten().count()
+    }
+
+    /// Size of the box `text` occupies when drawn with `style`.
+    pub fn measure(&self, text: &str, style: &TextStyle) -> (f32, f32) {
+        let lines = layout(&self.font, text, style);
+        (widest(&lines), lines.len() as f32 * self.line_height(style))
+    }
+
+    /// Queue `text` for the next frame with its top left corner at `position`.
+    pub fn queue(&mut self, text: &str, position: [f32; 2], style: &TextStyle) {
+        let px = style.size.round().max(1.0) as u32;
+        let lines = layout(&self.font, text, style);
+        let line_height = self.line_height(style);
+        let ascent = self
+            .font
+            .horizontal_line_metrics(px as f32)
+            .map_or(px as f32, |metrics| metrics.ascent);
+        let box_width = style.max_width.unwrap_or_else(|| widest(&lines));
+
+        for (row, line) in lines.iter().enumerate() {
+            let offset = align_offset(style.align, box_width, line.width);
+            let baseline = position[1] + ascent + row as f32 * line_height;
+
+            for &(ch, pen_x) in &line.glyphs {
+                let Some(glyph) = self.atlas.glyph(&self.font, ch, px) else {
+                    continue;
+                };
+                let metrics = glyph.metrics;
+                if metrics.width == 0 || metrics.height == 0 {
+                    continue;
+                }
+
+                // snap to whole pixels so glyphs stay crisp
+                let left = (position[0] + offset + pen_x).round() + metrics.xmin as f32;
+                let top = baseline.round() - (metrics.ymin + metrics.height as i32) as f32;
+                let right = left + metrics.width as f32;
+                let bottom = top + metrics.height as f32;
+                let [u0, v0, u1, v1] = glyph.uv;
+
+                self.vertices.extend_from_slice(&[
+                    TextVertex {
+                        position: [left, top],
+                        uv: [u0, v0],
+                        color: style.color,
+                    },
+                    TextVertex {
+                        position: [left, bottom],
+                        uv: [u0, v1],
+                        color: style.color,
+                    },
+                    TextVertex {
+                        position: [right, bottom],
+                        uv: [u1, v1],
+                        color: style.color,
+                    },
+                    TextVertex {
+                        position: [right, top],
+                        uv: [u1, v0],
+                        color: style.color,
+                    },
+                ]);
+            }
+        }
+    }
+
+    fn line_height(&self, style: &TextStyle) -> f32 {
+        let px = style.size.round().max(1.0);
+        let height = self
+            .font
+            .horizontal_line_metrics(px)
+            .map_or(px * 1.2, |metrics| metrics.new_line_size);
+        height * style.line_spacing
+    }
+}
+
+/// The horizontal font metrics layout needs, so it can run without a real font.
+trait Advance {
+    fn advance_width(&self, ch: char, px: f32) -> f32;
+    fn kern(&self, prev: char, ch: char, px: f32) -> Option<f32>;
+}
+
+impl Advance for Font {
+    fn advance_width(&self, ch: char, px: f32) -> f32 {
+        self.metrics(ch, px).advance_width
+    }
+
+    fn kern(&self, prev: char, ch: char, px: f32) -> Option<f32> {
+        self.horizontal_kern(prev, ch, px)
+    }
+}
+
+/// Break `text` into lines on `\n` and, with a `max_width`, at the last
+/// space that still fits. Words wider than `max_width` get a line of their own.
+fn layout(font: &impl Advance, text: &str, style: &TextStyle) -> Vec<Line> {
+    let px = style.size.round().max(1.0);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = Line::default();
+        let mut pen = 0.0;
+        let mut prev = None;
+
+        for word in paragraph.split_inclusive(' ') {
+            let trimmed = word.trim_end_matches(' ');
+
+            if let Some(max_width) = style.max_width {
+                let mut end = pen;
+                let mut last = prev;
+                for ch in trimmed.chars() {
+                    end += advance(font, last, ch, px);
+                    last = Some(ch);
+                }
+                if !line.glyphs.is_empty() && end > max_width {
+                    lines.push(std::mem::take(&mut line));
+                    pen = 0.0;
+                    prev = None;
+                }
+            }
+
+            for ch in word.chars() {
+                if let Some(kern) = prev.and_then(|p| font.kern(p, ch, px)) {
+                    pen += kern;
+                }
+                line.glyphs.push((ch, pen));
+                pen += font.advance_width(ch, px);
+                prev = Some(ch);
+                if ch != ' ' {
+                    line.width = pen;
+                }
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+fn advance(font: &impl Advance, prev: Option<char>, ch: char, px: f32) -> f32 {
+    let kern = prev.and_then(|p| font.kern(p, ch, px)).unwrap_or(0.0);
+    kern + font.advance_width(ch, px)
+}
+
+/// Width of the widest line.
+fn widest(lines: &[Line]) -> f32 {
+    lines.iter().map(|line| line.width).fold(0.0, f32::max)
+}
+
+/// How far right a line of `width` starts in a box `box_width` wide.
+fn align_offset(align: Align, box_width: f32, width: f32) -> f32 {
+    match align {
+        Align::Left => 0.0,
+        Align::Center => (box_width - width) / 2.0,
+        Align::Right => box_width - width,
+    }
+}
+
+/// GPU side of the text subsystem: the atlas texture and a single batched
+/// draw call for every glyph queued this frame.
+pub struct TextPipeline {
+    render_pipeline: wgpu::RenderPipeline,
+    atlas_texture: wgpu::Texture,
+    screen_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    /// Number of quads the vertex and index buffers can hold.
+    capacity: usize,
+    num_indices: u32,
+}
+
+impl TextPipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        text: &mut Text,
+    ) -> Self {
+        let atlas_texture = device.create_texture(&TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: Extent3d {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let atlas_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let atlas_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let screen_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Text Screen Buffer"),
+            size: size_of::<[f32; 4]>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Text Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: screen_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&atlas_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&atlas_sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("text.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Text Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[TextVertex::desc()],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+
+        let capacity = 256;
+        let (vertex_buffer, index_buffer) = create_quad_buffers(device, capacity);
+
+        // a fresh texture needs every glyph rasterized so far
+        text.atlas.mark_dirty(0, ATLAS_SIZE);
+
+        let pipeline = Self {
+            render_pipeline,
+            atlas_texture,
+            screen_buffer,
+            bind_group,
+            vertex_buffer,
+            index_buffer,
+            capacity,
+            num_indices: 0,
+        };
+        pipeline.upload_atlas(queue, text);
+        pipeline
+    }
+
+    /// Upload the glyphs queued since the last frame and whatever part of the
+    /// atlas changed, then clear the queue.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        text: &mut Text,
+        size: PhysicalSize<u32>,
+    ) {
+        self.upload_atlas(queue, text);
+
+        queue.write_buffer(
+            &self.screen_buffer,
+            0,
+            cast_slice(&[size.width as f32, size.height as f32, 0.0, 0.0]),
+        );
+
+        let quads = text.vertices.len() / 4;
+        if quads > self.capacity {
+            self.capacity = quads.next_power_of_two();
+            (self.vertex_buffer, self.index_buffer) = create_quad_buffers(device, self.capacity);
+        }
+        if quads > 0 {
+            queue.write_buffer(&self.vertex_buffer, 0, cast_slice(&text.vertices));
+        }
+        self.num_indices = (quads * 6) as u32;
+        text.vertices.clear();
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        if self.num_indices == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+    }
+
+    fn upload_atlas(&self, queue: &wgpu::Queue, text: &mut Text) {
+        let Some((start, end)) = text.atlas.dirty.take() else {
+            return;
+        };
+        let offset = (start * ATLAS_SIZE) as usize;
+        let len = ((end - start) * ATLAS_SIZE) as usize;
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.atlas_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: start,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            &text.atlas.pixels[offset..offset + len],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(ATLAS_SIZE),
+                rows_per_image: Some(end - start),
+            },
+            Extent3d {
+                width: ATLAS_SIZE,
+                height: end - start,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+/// Vertex buffer for `capacity` quads and the matching index buffer.
+fn create_quad_buffers(device: &wgpu::Device, capacity: usize) -> (wgpu::Buffer, wgpu::Buffer) {
+    use wgpu::util::{BufferInitDescriptor, DeviceExt};
+
+    let vertex_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("Text Vertex Buffer"),
+        size: (capacity * 4 * size_of::<TextVertex>()) as BufferAddress,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let indices: Vec<u32> = (0..capacity as u32)
+        .flat_map(|quad| {
+            let i = quad * 4;
+            [i, i + 1, i + 2, i, i + 2, i + 3]
+        })
+        .collect();
+    let index_buffer = device.create_buffer_init(&BufferInitDescriptor {
+        label: Some("Text Index Buffer"),
+        contents: cast_slice(&indices),
+        usage: BufferUsages::INDEX,
+    });
+
+    (vertex_buffer, index_buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character 10 pixels wide at size 10, without kerning.
+    struct Fixed;
+
+    impl Advance for Fixed {
+        fn advance_width(&self, _ch: char, px: f32) -> f32 {
+            px
+        }
+
+        fn kern(&self, _prev: char, _ch: char, _px: f32) -> Option<f32> {
+            None
+        }
+    }
+
+    fn style(max_width: Option<f32>) -> TextStyle {
+        TextStyle {
+            size: 10.0,
+            max_width,
+            ..TextStyle::default()
+        }
+    }
+
+    fn lines(text: &str, max_width: Option<f32>) -> Vec<(String, f32)> {
+        layout(&Fixed, text, &style(max_width))
+            .into_iter()
+            .map(|line| (line.glyphs.iter().map(|&(ch, _)| ch).collect(), line.width))
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces_and_newlines() {
+        // trailing spaces stay on the line but don't count toward its width
+        assert_eq!(
+            lines("one two three", Some(70.0)),
+            [("one two ".to_owned(), 70.0), ("three".to_owned(), 50.0)]
+        );
+        // a word ending exactly at the limit still fits
+        assert_eq!(lines("ab cd", Some(50.0)), [("ab cd".to_owned(), 50.0)]);
+        assert_eq!(
+            lines("ab\ncd ef", None),
+            [("ab".to_owned(), 20.0), ("cd ef".to_owned(), 50.0)]
+        );
+        let glyphs = &layout(&Fixed, "a b", &style(None))[0].glyphs;
+        assert_eq!(glyphs, &[('a', 0.0), (' ', 10.0), ('b', 20.0)]);
+    }
+
+    #[test]
+    fn long_words_get_a_line_of_their_own() {
+        assert_eq!(
+            lines("a abcdefgh b", Some(40.0)),
+            [
+                ("a ".to_owned(), 10.0),
+                ("abcdefgh ".to_owned(), 80.0),
+                ("b".to_owned(), 10.0)
+            ]
+        );
+        assert_eq!(
+            lines("abcdefgh", Some(40.0)),
+            [("abcdefgh".to_owned(), 80.0)]
+        );
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_line() {
+        assert_eq!(lines("", Some(40.0)), [(String::new(), 0.0)]);
+        assert_eq!(
+            lines("\n", None),
+            [(String::new(), 0.0), (String::new(), 0.0)]
+        );
+        assert_eq!(widest(&layout(&Fixed, "", &style(None))), 0.0);
+    }
+
+    #[test]
+    fn aligns_lines_in_the_box() {
+        let lines = layout(&Fixed, "abcd\nab", &style(None));
+        let box_width = widest(&lines);
+        assert_eq!(box_width, 40.0);
+        let offsets = |align| {
+            lines
+                .iter()
+                .map(|line| align_offset(align, box_width, line.width))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offsets(Align::Left), [0.0, 0.0]);
+        assert_eq!(offsets(Align::Center), [0.0, 10.0]);
+        assert_eq!(offsets(Align::Right), [0.0, 20.0]);
+    }
+
+    #[test]
+    fn a_full_atlas_drops_glyphs_once() {
+        let font_bytes = include_bytes!("../assets/fonts/DejaVuSans.ttf") as &[u8];
+        let font = Font::from_bytes(font_bytes, FontSettings::default()).unwrap();
+        let mut atlas = GlyphAtlas::new();
+        atlas.cursor_y = ATLAS_SIZE;
+
+        assert!(atlas.glyph(&font, 'a', 16).is_none());
+        assert!(atlas.full);
+        assert!(atlas.glyphs[&GlyphKey { ch: 'a', px: 16 }].is_none());
+        assert!(atlas.glyph(&font, 'a', 16).is_none());
+        assert_eq!(atlas.dirty, None);
+    }
+}
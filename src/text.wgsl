@@ -0,0 +1,39 @@
+struct Screen {
+  size: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> screen: Screen;
+@group(0) @binding(1)
+var atlas: texture_2d<f32>;
+@group(0) @binding(2)
+var atlas_sampler: sampler;
+
+struct VertexInput {
+  @location(0) position: vec2<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) color: vec4<f32>
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>
+}
+
+@vertex
+fn vert_main(glyph: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  // pixels with the origin at the top left -> clip space
+  let ndc = glyph.position / screen.size * 2.0 - 1.0;
+  out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+  out.uv = glyph.uv;
+  out.color = glyph.color;
+  return out;
+}
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let coverage = textureSample(atlas, atlas_sampler, in.uv).r;
+  return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,486 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem::size_of,
+    ops::Range,
+};
+
+use bytemuck::cast_slice;
+use egui::{
+    epaint::{ImageData, Primitive, Vertex},
+    ClippedPrimitive, Color32, TextureFilter, TextureId, TextureOptions, TexturesDelta,
+};
+use wgpu::{
+    include_wgsl, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutEntry, BlendComponent,
+    BlendFactor, BlendOperation, BlendState, BufferAddress, BufferDescriptor, BufferUsages,
+    ColorWrites, Extent3d, FragmentState, MultisampleState, PipelineLayoutDescriptor,
+    PrimitiveState, RenderPass, RenderPipelineDescriptor, ShaderStages, TextureAspect,
+    TextureDescriptor, TextureUsages, VertexAttribute, VertexBufferLayout, VertexState,
+    VertexStepMode,
+};
+use winit::{dpi::PhysicalSize, event::WindowEvent, window::Window};
+
+/// CPU-side copy of a texture egui asked for, kept so it can be re-uploaded
+/// to a new device.
+struct UiTexture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+    options: TextureOptions,
+}
+
+/// Immediate-mode debug UI: feeds window events to egui, runs the UI code
+/// every frame and keeps the resulting meshes for the [`UiPipeline`].
+pub struct Ui {
+    ctx: egui::Context,
+    state: egui_winit::State,
+    textures: HashMap<TextureId, UiTexture>,
+    /// Textures changed since the last upload.
+    dirty: HashSet<TextureId>,
+    /// Textures egui no longer needs.
+    freed: Vec<TextureId>,
+    primitives: Vec<ClippedPrimitive>,
+    pub visible: bool,
+}
+
+impl Ui {
+    pub fn new(window: &Window) -> Self {
+        let mut state = egui_winit::State::new(window);
+        state.set_pixels_per_point(window.scale_factor() as f32);
+
+        Self {
+            ctx: egui::Context::default(),
+            state,
+            textures: HashMap::new(),
+            dirty: HashSet::new(),
+            freed: Vec::new(),
+            primitives: Vec::new(),
+            visible: true,
+        }
+    }
+
+    /// Forward a window event to egui, returns `true` if the UI consumed it.
+    pub fn on_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+        self.state.on_event(&self.ctx, event).consumed
+    }
+
+    /// Run one frame of UI code. Nothing is drawn while the UI is hidden.
+    pub fn run(&mut self, window: &Window, build: impl FnOnce(&egui::Context)) {
+        if !self.visible {
+            self.primitives.clear();
+            return;
+        }
+
+        let input = self.state.take_egui_input(window);
+        let output = self.ctx.run(input, build);
+        self.state
+            .handle_platform_output(window, &self.ctx, output.platform_output);
+        self.apply_textures(output.textures_delta);
+        self.primitives = self.ctx.tessellate(output.shapes);
+    }
+
+    fn apply_textures(&mut self, delta: TexturesDelta) {
+        for (id, delta) in delta.set {
+            let (size, pixels): (_, Vec<Color32>) = match &delta.image {
+                ImageData::Color(image) => (image.size, image.pixels.clone()),
+                ImageData::Font(image) => (image.size, image.srgba_pixels(None).collect()),
+            };
+
+            match (delta.pos, self.textures.get_mut(&id)) {
+                (Some([x, y]), Some(texture)) => {
+                    let width = texture.size[0];
+                    for row in 0..size[1] {
+                        let dst = (y + row) * width + x;
+                        texture.pixels[dst..dst + size[0]]
+                            .copy_from_slice(&pixels[row * size[0]..(row + 1) * size[0]]);
+                    }
+                }
+                _ => {
+                    self.textures.insert(
+                        id,
+                        UiTexture {
+                            size,
+                            pixels,
+                            options: delta.options,
+                        },
+                    );
+                }
+            }
+            self.dirty.insert(id);
+        }
+
+        for id in delta.free {
+            self.textures.remove(&id);
+            self.dirty.remove(&id);
+            self.freed.push(id);
+        }
+    }
+}
+
+struct GpuTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    size: [usize; 2],
+}
+
+struct Draw {
+    texture: TextureId,
+    /// Scissor rectangle in physical pixels: x, y, width, height.
+    scissor: [u32; 4],
+    indices: Range<u32>,
+    base_vertex: i32,
+}
+
+/// GPU side of the debug UI, draws egui's meshes in a pass of their own.
+pub struct UiPipeline {
+    render_pipeline: wgpu::RenderPipeline,
+    screen_buffer: wgpu::Buffer,
+    screen_bind_group: wgpu::BindGroup,
+    texture_layout: wgpu::BindGroupLayout,
+    textures: HashMap<TextureId, GpuTexture>,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    draws: Vec<Draw>,
+}
+
+impl UiPipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        ui: &mut Ui,
+    ) -> Self {
+        let screen_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("UI Screen Buffer"),
+            size: size_of::<[f32; 4]>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let screen_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("UI Screen Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let screen_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("UI Screen Bind Group"),
+            layout: &screen_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: screen_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("UI Texture Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("ui.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("UI Pipeline Layout"),
+            bind_group_layouts: &[&screen_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        // egui hands out premultiplied alpha
+        let blend = BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::OneMinusSrcAlpha,
+            operation: BlendOperation::Add,
+        };
+
+        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("UI Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[VertexBufferLayout {
+                    array_stride: size_of::<Vertex>() as BufferAddress,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: &[
+                        VertexAttribute {
+                            offset: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 0,
+                        },
+                        VertexAttribute {
+                            offset: size_of::<[f32; 2]>() as BufferAddress,
+                            format: wgpu::VertexFormat::Float32x2,
+                            shader_location: 1,
+                        },
+                        VertexAttribute {
+                            offset: size_of::<[f32; 4]>() as BufferAddress,
+                            format: wgpu::VertexFormat::Unorm8x4,
+                            shader_location: 2,
+                        },
+                    ],
+                }],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: if format.is_srgb() {
+                    "frag_linear"
+                } else {
+                    "frag_gamma"
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(BlendState {
+                        color: blend,
+                        alpha: blend,
+                    }),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+
+        let (vertex_buffer, index_buffer) = create_mesh_buffers(device, 1024, 1024);
+
+        // a fresh device needs every texture egui still holds on to
+        ui.dirty.extend(ui.textures.keys().copied());
+        ui.freed.clear();
+
+        let mut pipeline = Self {
+            render_pipeline,
+            screen_buffer,
+            screen_bind_group,
+            texture_layout,
+            textures: HashMap::new(),
+            vertex_buffer,
+            index_buffer,
+            draws: Vec::new(),
+        };
+        pipeline.upload_textures(device, queue, ui);
+        pipeline
+    }
+
+    /// Upload changed textures and this frame's meshes.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        ui: &mut Ui,
+        size: PhysicalSize<u32>,
+    ) {
+        self.upload_textures(device, queue, ui);
+
+        let pixels_per_point = ui.ctx.pixels_per_point();
+        queue.write_buffer(
+            &self.screen_buffer,
+            0,
+            cast_slice(&[
+                size.width as f32 / pixels_per_point,
+                size.height as f32 / pixels_per_point,
+                0.0,
+                0.0,
+            ]),
+        );
+
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        self.draws.clear();
+
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &ui.primitives
+        {
+            let Primitive::Mesh(mesh) = primitive else {
+                // paint callbacks are not supported
+                continue;
+            };
+
+            let min_x = (clip_rect.min.x * pixels_per_point).round().max(0.0) as u32;
+            let min_y = (clip_rect.min.y * pixels_per_point).round().max(0.0) as u32;
+            let max_x = ((clip_rect.max.x * pixels_per_point).round() as u32).min(size.width);
+            let max_y = ((clip_rect.max.y * pixels_per_point).round() as u32).min(size.height);
+            if max_x <= min_x || max_y <= min_y || mesh.indices.is_empty() {
+                continue;
+            }
+
+            let start = indices.len() as u32;
+            self.draws.push(Draw {
+                texture: mesh.texture_id,
+                scissor: [min_x, min_y, max_x - min_x, max_y - min_y],
+                indices: start..start + mesh.indices.len() as u32,
+                base_vertex: vertices.len() as i32,
+            });
+            vertices.extend_from_slice(&mesh.vertices);
+            indices.extend_from_slice(&mesh.indices);
+        }
+
+        let vertex_size = (vertices.len() * size_of::<Vertex>()) as BufferAddress;
+        let index_size = (indices.len() * size_of::<u32>()) as BufferAddress;
+        if vertex_size > self.vertex_buffer.size() || index_size > self.index_buffer.size() {
+            (self.vertex_buffer, self.index_buffer) = create_mesh_buffers(
+                device,
+                vertex_size
+                    .max(self.vertex_buffer.size())
+                    .next_power_of_two(),
+                index_size.max(self.index_buffer.size()).next_power_of_two(),
+            );
+        }
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, cast_slice(&vertices));
+            queue.write_buffer(&self.index_buffer, 0, cast_slice(&indices));
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        if self.draws.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.screen_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        for draw in &self.draws {
+            let Some(texture) = self.textures.get(&draw.texture) else {
+                continue;
+            };
+            let [x, y, width, height] = draw.scissor;
+            render_pass.set_scissor_rect(x, y, width, height);
+            render_pass.set_bind_group(1, &texture.bind_group, &[]);
+            render_pass.draw_indexed(draw.indices.clone(), draw.base_vertex, 0..1);
+        }
+    }
+
+    fn upload_textures(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, ui: &mut Ui) {
+        for id in ui.freed.drain(..) {
+            self.textures.remove(&id);
+        }
+
+        for id in ui.dirty.drain() {
+            let Some(image) = ui.textures.get(&id) else {
+                continue;
+            };
+            let [width, height] = image.size;
+            let extent = Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            };
+
+            if self.textures.get(&id).map(|t| t.size) != Some(image.size) {
+                let texture = device.create_texture(&TextureDescriptor {
+                    label: Some("UI Texture"),
+                    size: extent,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                    view_formats: &[],
+                });
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+                    label: Some("UI Sampler"),
+                    mag_filter: filter_mode(image.options.magnification),
+                    min_filter: filter_mode(image.options.minification),
+                    ..Default::default()
+                });
+                let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("UI Texture Bind Group"),
+                    layout: &self.texture_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&view),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&sampler),
+                        },
+                    ],
+                });
+                self.textures.insert(
+                    id,
+                    GpuTexture {
+                        texture,
+                        bind_group,
+                        size: image.size,
+                    },
+                );
+            }
+
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.textures[&id].texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                cast_slice(&image.pixels),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width as u32),
+                    rows_per_image: Some(height as u32),
+                },
+                extent,
+            );
+        }
+    }
+}
+
+fn filter_mode(filter: TextureFilter) -> wgpu::FilterMode {
+    match filter {
+        TextureFilter::Nearest => wgpu::FilterMode::Nearest,
+        TextureFilter::Linear => wgpu::FilterMode::Linear,
+    }
+}
+
+fn create_mesh_buffers(
+    device: &wgpu::Device,
+    vertex_size: BufferAddress,
+    index_size: BufferAddress,
+) -> (wgpu::Buffer, wgpu::Buffer) {
+    let vertex_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("UI Vertex Buffer"),
+        size: vertex_size,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let index_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("UI Index Buffer"),
+        size: index_size,
+        usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    (vertex_buffer, index_buffer)
+}
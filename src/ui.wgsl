@@ -0,0 +1,64 @@
+struct Screen {
+  // size of the target in points
+  size: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> screen: Screen;
+
+@group(1) @binding(0)
+var ui_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var ui_sampler: sampler;
+
+struct VertexInput {
+  @location(0) position: vec2<f32>,
+  @location(1) uv: vec2<f32>,
+  // sRGB with premultiplied alpha
+  @location(2) color: vec4<f32>
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>
+}
+
+fn linear_from_gamma(srgb: vec3<f32>) -> vec3<f32> {
+  let cutoff = srgb < vec3<f32>(0.04045);
+  let lower = srgb / vec3<f32>(12.92);
+  let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+  return select(higher, lower, cutoff);
+}
+
+fn gamma_from_linear(rgb: vec3<f32>) -> vec3<f32> {
+  let cutoff = rgb < vec3<f32>(0.0031308);
+  let lower = rgb * vec3<f32>(12.92);
+  let higher = vec3<f32>(1.055) * pow(rgb, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+  return select(higher, lower, cutoff);
+}
+
+@vertex
+fn vert_main(vertex: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  // points with the origin at the top left -> clip space
+  let ndc = vertex.position / screen.size * 2.0 - 1.0;
+  out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+  out.uv = vertex.uv;
+  out.color = vertex.color;
+  return out;
+}
+
+// for *Srgb targets, the hardware encodes the linear output
+@fragment
+fn frag_linear(in: VertexOutput) -> @location(0) vec4<f32> {
+  let color = vec4<f32>(linear_from_gamma(in.color.rgb), in.color.a);
+  return color * textureSample(ui_texture, ui_sampler, in.uv);
+}
+
+// for non-srgb targets, blend in gamma space like egui expects
+@fragment
+fn frag_gamma(in: VertexOutput) -> @location(0) vec4<f32> {
+  let texel = textureSample(ui_texture, ui_sampler, in.uv);
+  return in.color * vec4<f32>(gamma_from_linear(texel.rgb), texel.a);
+}